@@ -0,0 +1,39 @@
+use std::process::Command;
+
+use chrono::{SecondsFormat, TimeZone, Utc};
+
+/// Captures the git commit and the build time, which are reported by the
+/// `/version` endpoint.
+fn main() {
+    // GIT_COMMIT takes precedence, for builds without a git checkout (such
+    // as the Docker build).
+    let commit = std::env::var("GIT_COMMIT").ok()
+        .filter(|commit| !commit.is_empty())
+        .or_else(git_commit)
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // SOURCE_DATE_EPOCH allows for reproducible builds.
+    let built_at = std::env::var("SOURCE_DATE_EPOCH").ok()
+        .and_then(|epoch| epoch.parse::<i64>().ok())
+        .and_then(|epoch| Utc.timestamp_opt(epoch, 0).single())
+        .unwrap_or_else(Utc::now);
+
+    println!("cargo:rustc-env=DODEMANSKNOP_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=DODEMANSKNOP_BUILD_TIMESTAMP={}", built_at.to_rfc3339_opts(SecondsFormat::Secs, true));
+
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+}
+
+fn git_commit() -> Option<String> {
+    Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|commit| commit.trim().to_string())
+}
@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+
+/// Source of the current time for the scheduler, the alerter (maintenance
+/// windows and the retry queue) and notifiers that write timestamps.
+/// Deadlines are computed from this clock, while timers sleep on tokio's
+/// clock; a clock that follows tokio's clock (like `FakeClock`) therefore
+/// lets the whole schedule be driven by `tokio::time::pause` and
+/// `tokio::time::advance`, without waiting in real time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system's wall clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock for tests that starts at a fixed time and then follows tokio's
+/// clock. With `tokio::time::pause`, time only passes through
+/// `tokio::time::advance` (or auto-advancing while all tasks are idle), which
+/// moves deadlines computed from this clock and timers alike.
+#[cfg(test)]
+pub struct FakeClock {
+    start: DateTime<Utc>,
+    base: tokio::time::Instant,
+}
+
+#[cfg(test)]
+impl FakeClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            start,
+            base: tokio::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now(&self) -> DateTime<Utc> {
+        let elapsed = tokio::time::Instant::now() - self.base;
+        self.start + chrono::Duration::from_std(elapsed).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn fake_clock_follows_paused_tokio_time() {
+        let start = Utc::now();
+        let clock = FakeClock::new(start);
+
+        assert_eq!(clock.now(), start);
+
+        tokio::time::advance(std::time::Duration::from_secs(90)).await;
+        assert_eq!(clock.now(), start + chrono::Duration::seconds(90));
+    }
+}
@@ -1,50 +1,2376 @@
-use config::{Config, File, ConfigError, Environment};
-use serde::Deserialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use config::{Config, File, FileFormat, ConfigError, Environment, ValueKind};
+use cron::Schedule;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::notifier::{self, AlertEvent};
+
+/// Interval (in seconds) that is used when neither the check itself nor the
+/// global settings specify one.
+pub const DEFAULT_INTERVAL_SECONDS: i64 = 30;
+
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECONDS: u64 = 10;
+
+/// Types of the notifiers that can send heartbeats; all others ignore them.
+const HEARTBEAT_NOTIFIER_TYPES: &[&str] = &["webhook", "slack", "file", "stdout", "mqtt", "noop"];
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Settings {
     pub notifiers: Vec<NotifierSettings>,
+
+    /// Deprecated alias for `default_interval_seconds`.
     pub timeout: Option<i64>,
+
+    /// Interval within which a check is expected to ping, unless the check
+    /// specifies its own interval in `checks`.
+    pub default_interval_seconds: Option<i64>,
+
+    /// Additional time (in seconds) a check may be late before the first
+    /// alert fires, to tolerate network jitter; alerts fire only after
+    /// `interval + grace` has elapsed since the last ping. Defaults to zero,
+    /// and may be overridden per check.
+    pub grace_seconds: Option<i64>,
+
+    /// When set, an alerted check only recovers on a ping that arrives at
+    /// least this many seconds before the deadline set by its previous
+    /// ping; later pings re-arm its timer, but keep it alerted. This keeps
+    /// checks that ping right at their deadline from flapping. May be
+    /// overridden per check.
+    pub recovery_margin_seconds: Option<i64>,
+
+    /// Names of the notifiers that alerts are sent to, unless a check
+    /// specifies its own. When unset, alerts are sent to all notifiers.
+    pub default_notifiers: Option<Vec<String>>,
+
+    #[serde(default)]
+    pub checks: HashMap<String, CheckConfig>,
+
+    #[serde(default)]
+    pub server: ServerSettings,
+
+    /// Number of pings that may be waiting for the ping receiver; see
+    /// [`Settings::ping_queue_capacity`].
+    pub ping_queue_capacity: Option<usize>,
+
+    /// Maximum time (in seconds) to wait for pending alerts to be sent when
+    /// shutting down.
+    pub shutdown_timeout_seconds: Option<u64>,
+
+    /// Minimum time (in seconds) between two failure notifications for the
+    /// same check and notifiers. Repeated failures within this time are
+    /// suppressed; a recovery ends the cooldown. Disabled by default.
+    pub alert_cooldown_seconds: Option<u64>,
+
+    /// Time (in seconds) after startup during which declared checks that
+    /// never pinged are not alerted, so that their clients can report in
+    /// after the monitor comes up; unlike `grace_seconds`, this applies only
+    /// once. Disabled by default.
+    pub startup_grace_seconds: Option<u64>,
+
+    /// Whether to check at startup that all notifiers can reach their
+    /// destinations.
+    #[serde(default)]
+    pub verify_notifier_on_startup: bool,
+
+    /// Whether a failed startup verification aborts startup, instead of
+    /// only being logged.
+    #[serde(default)]
+    pub fail_on_verify: bool,
+
+    /// Maximum number of notifications that are sent at the same time.
+    #[serde(default = "default_notifier_concurrency")]
+    pub notifier_concurrency: usize,
+
+    /// Maximum time (in seconds) a single call of a notifier may take; a
+    /// call that takes longer is cut off and counts as a temporary failure,
+    /// so that it is retried and trips circuit breakers.
+    #[serde(default = "default_notifier_send_timeout_seconds")]
+    pub notifier_send_timeout_seconds: u64,
+
+    /// When set, failures are collected for this many seconds after the
+    /// first one, and then notified together. Disabled by default.
+    pub alert_batch_seconds: Option<u64>,
+
+    /// Recurring time ranges during which failure notifications are
+    /// suppressed, e.g. for scheduled maintenance.
+    #[serde(default)]
+    pub maintenance_windows: Vec<MaintenanceWindowSettings>,
+
+    /// When set, a summary of all checks is sent periodically, as proof
+    /// that dodemansknop itself is running.
+    pub heartbeat: Option<HeartbeatSettings>,
+
+    /// When set, notifications that failed with a temporary error are
+    /// stored on disk and retried until they are delivered.
+    pub retry_queue: Option<RetryQueueSettings>,
+
+    pub auth: Option<AuthSettings>,
+
+    /// Path of a JSON file in which the state of all checks is persisted,
+    /// so that it survives restarts. Shorthand for a "json" state backend.
+    pub state_file: Option<String>,
+
+    /// Where the state of all checks is persisted. Only kept in memory when
+    /// neither this nor `state_file` is set.
+    pub state_backend: Option<StateBackendSettings>,
+
+    #[serde(default)]
+    pub log: LogSettings,
+
+    /// Default log level; see [`Settings::log_level`].
+    pub log_level: Option<LogLevel>,
+
+    /// Number of recent events (pings, alerts, recoveries, ...) that are
+    /// kept in memory and served by `GET /events`. Zero disables the event
+    /// log.
+    #[serde(default = "default_event_log_size")]
+    pub event_log_size: usize,
+
+    /// When set, the API is served over HTTPS instead of HTTP.
+    pub tls: Option<TlsSettings>,
+
+    /// Allows browser-based clients on other origins to call the API.
+    pub cors: Option<CorsSettings>,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum StateBackendSettings {
+    /// A single JSON file, rewritten on every change.
+    Json { path: String },
+
+    /// An SQLite database with a row per check.
+    Sqlite { path: String },
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RetryQueueSettings {
+    /// Path of the file (JSON lines) in which queued notifications are
+    /// stored, so that they survive restarts.
+    pub path: String,
+
+    /// Delay (in seconds) before the first retry; it doubles with each
+    /// further attempt.
+    #[serde(default = "default_retry_queue_base_delay_seconds")]
+    pub base_delay_seconds: u64,
+
+    /// Upper bound for the delay between two retries, in seconds.
+    #[serde(default = "default_retry_queue_max_delay_seconds")]
+    pub max_delay_seconds: u64,
+
+    /// Time (in seconds) after the first failure after which a notification
+    /// is dropped from the queue.
+    #[serde(default = "default_retry_queue_max_age_seconds")]
+    pub max_age_seconds: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct HeartbeatSettings {
+    /// Time (in seconds) between two heartbeats.
+    pub interval_seconds: u64,
+
+    /// Names of the notifiers that heartbeats are sent to. When unset,
+    /// heartbeats are sent to all notifiers that support them.
+    pub notifiers: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MaintenanceWindowSettings {
+    /// Cron expression (with a leading seconds field, evaluated in UTC) at
+    /// which the window starts.
+    pub cron: String,
+
+    /// Length of the window, in minutes.
+    pub duration_minutes: u64,
+}
+
+impl MaintenanceWindowSettings {
+    /// Parses the window's cron expression.
+    pub fn window(&self) -> Result<MaintenanceWindow, cron::error::Error> {
+        Ok(MaintenanceWindow {
+            schedule: Schedule::from_str(&self.cron)?,
+            duration: chrono::Duration::minutes(self.duration_minutes as i64),
+        })
+    }
+}
+
+/// A maintenance window whose schedule has been parsed, as used by the
+/// alerter.
+#[derive(Debug, Clone)]
+pub struct MaintenanceWindow {
+    schedule: Schedule,
+    duration: chrono::Duration,
+}
+
+impl MaintenanceWindow {
+    /// Whether the window is open at `at`, i.e. whether it started less than
+    /// its duration before.
+    pub fn is_active(&self, at: DateTime<Utc>) -> bool {
+        self.schedule.after(&(at - self.duration)).next().is_some_and(|start| start <= at)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CorsSettings {
+    /// Origins (e.g. "https://dashboard.example.com") that may call the API,
+    /// or "*" for any origin. CORS is disabled when this is empty.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+
+    #[serde(default = "default_cors_allowed_methods")]
+    pub allowed_methods: Vec<String>,
+
+    #[serde(default = "default_cors_allowed_headers")]
+    pub allowed_headers: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TlsSettings {
+    /// Path of a PEM file containing the certificate chain.
+    pub cert_path: Option<String>,
+
+    /// Path of a PEM file containing the private key.
+    pub key_path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct LogSettings {
+    #[serde(default)]
+    pub format: LogFormat,
+
+    /// Deprecated location of the top-level `log_level`.
+    pub level: Option<LogLevel>,
+}
+
+/// Log level, as accepted in the configuration file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl TryFrom<String> for LogLevel {
+    type Error = String;
+
+    fn try_from(level: String) -> Result<Self, String> {
+        match level.to_lowercase().as_str() {
+            "error" => Ok(LogLevel::Error),
+            "warn" => Ok(LogLevel::Warn),
+            "info" => Ok(LogLevel::Info),
+            "debug" => Ok(LogLevel::Debug),
+            "trace" => Ok(LogLevel::Trace),
+            _ => Err(format!("unsupported log level \"{}\"; must be error, warn, info, debug or trace", level)),
+        }
+    }
+}
+
+impl LogLevel {
+    pub fn filter(&self) -> log::LevelFilter {
+        match self {
+            LogLevel::Error => log::LevelFilter::Error,
+            LogLevel::Warn => log::LevelFilter::Warn,
+            LogLevel::Info => log::LevelFilter::Info,
+            LogLevel::Debug => log::LevelFilter::Debug,
+            LogLevel::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+/// Format of the log output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// Human-readable lines, as printed by env_logger.
+    #[default]
+    Text,
+
+    /// One JSON object per line, with structured fields such as the check
+    /// ID.
+    Json,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AuthSettings {
+    /// When set, pings need to carry an `Authorization: Bearer <token>` header.
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ServerSettings {
+    /// IPv4 or IPv6 address literal to bind the HTTP server to.
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+
+    #[serde(default = "default_port")]
+    pub port: u16,
+
+    /// Whether pings may also be sent with GET instead of POST, for clients
+    /// that cannot issue POST requests.
+    #[serde(default)]
+    pub allow_get_ping: bool,
+
+    /// Maximum number of IDs accepted by a single bulk ping request.
+    #[serde(default = "default_max_ping_batch_size")]
+    pub max_ping_batch_size: usize,
+
+    /// Maximum number of pings per second accepted for each check; excess
+    /// pings are rejected with 429. Unlimited when unset.
+    pub ping_rate_limit: Option<f64>,
+
+    /// Deprecated location of the top-level `ping_queue_capacity`.
+    pub ping_queue_capacity: Option<usize>,
+
+    /// Maximum time (in milliseconds) a ping waits for room in a full queue
+    /// before it is rejected with 503.
+    #[serde(default = "default_ping_enqueue_timeout_ms")]
+    pub ping_enqueue_timeout_ms: u64,
+
+    /// Regular expression that the IDs of pinged checks must match in full;
+    /// pings for other IDs are rejected with 400.
+    #[serde(default = "default_check_id_pattern")]
+    pub check_id_pattern: String,
+
+    /// Maximum number of discovered checks that are tracked; pings for
+    /// further unknown IDs are rejected with 507, so that clients sending
+    /// random IDs cannot exhaust memory. Declared checks do not count
+    /// towards the limit, and are always accepted. Unlimited when unset.
+    pub max_checks: Option<usize>,
+
+    /// Whether successful pings are always answered with a JSON body
+    /// stating the check's next deadline. Otherwise, only requests that
+    /// accept `application/json` get one.
+    #[serde(default)]
+    pub ping_json_response: bool,
+
+    /// Window (in milliseconds) within which further pings for a check are
+    /// taken for duplicates of the first one, e.g. from client retries, and
+    /// answered without being processed. Disabled with 0 (the default).
+    #[serde(default)]
+    pub ping_debounce_ms: u64,
+
+    /// Addresses to serve parts of the API on, e.g. to expose pings on one
+    /// port and the management endpoints on another. When empty, the whole
+    /// API is served on `bind_address` and `port`.
+    #[serde(default)]
+    pub listeners: Vec<ListenerSettings>,
+
+    /// Whether `POST /admin/reset` may be used to forget all checks. As this
+    /// cannot be undone, it is disabled by default.
+    #[serde(default)]
+    pub allow_admin_reset: bool,
+}
+
+/// Parts of the API that a listener serves.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteSet {
+    /// All endpoints.
+    #[default]
+    All,
+
+    /// Pings, and the health endpoints.
+    Ping,
+
+    /// Everything except pings: checks, events, metrics, health and test
+    /// notifications.
+    Admin,
+}
+
+impl RouteSet {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RouteSet::All => "all",
+            RouteSet::Ping => "ping",
+            RouteSet::Admin => "admin",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ListenerSettings {
+    /// IPv4 or IPv6 address literal to bind to.
+    #[serde(default = "default_bind_address")]
+    pub bind_address: String,
+
+    pub port: u16,
+
+    #[serde(default)]
+    pub routes: RouteSet,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            bind_address: default_bind_address(),
+            port: default_port(),
+            allow_get_ping: false,
+            max_ping_batch_size: default_max_ping_batch_size(),
+            ping_rate_limit: None,
+            ping_queue_capacity: None,
+            ping_enqueue_timeout_ms: default_ping_enqueue_timeout_ms(),
+            check_id_pattern: default_check_id_pattern(),
+            max_checks: None,
+            ping_json_response: false,
+            ping_debounce_ms: 0,
+            listeners: Vec::new(),
+            allow_admin_reset: false,
+        }
+    }
+}
+
+impl ServerSettings {
+    pub fn socket_addr(&self) -> Result<SocketAddr, String> {
+        parse_socket_addr(&self.bind_address, self.port)
+    }
+
+    /// Returns the addresses to listen on, with the routes to serve on each.
+    pub fn listen_addrs(&self) -> Result<Vec<(SocketAddr, RouteSet)>, String> {
+        if self.listeners.is_empty() {
+            return Ok(vec![(self.socket_addr()?, RouteSet::All)]);
+        }
+
+        self.listeners.iter()
+            .map(|l| Ok((parse_socket_addr(&l.bind_address, l.port)?, l.routes)))
+            .collect()
+    }
+
+    pub fn ping_debounce(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.ping_debounce_ms)
+    }
+
+    pub fn ping_enqueue_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.ping_enqueue_timeout_ms)
+    }
+
+    /// Compiles `check_id_pattern`, anchored so that it has to match the
+    /// whole ID.
+    pub fn check_id_regex(&self) -> Result<Regex, regex::Error> {
+        Regex::new(&format!("^(?:{})$", self.check_id_pattern))
+    }
+}
+
+fn parse_socket_addr(bind_address: &str, port: u16) -> Result<SocketAddr, String> {
+    let addr = bind_address.trim_start_matches('[').trim_end_matches(']');
+
+    match addr.parse::<IpAddr>() {
+        Ok(ip) => Ok(SocketAddr::new(ip, port)),
+        Err(e) => Err(format!("invalid bind address '{}': {}", bind_address, e)),
+    }
+}
+
+fn default_bind_address() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_port() -> u16 {
+    3030
+}
+
+fn default_max_ping_batch_size() -> usize {
+    100
+}
+
+fn default_ping_queue_capacity() -> usize {
+    32
+}
+
+fn default_ping_enqueue_timeout_ms() -> u64 {
+    100
+}
+
+fn default_check_id_pattern() -> String {
+    "[A-Za-z0-9_.:-]{1,128}".to_string()
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CheckConfig {
+    pub interval_seconds: Option<i64>,
+
+    /// Cron expression (with a leading seconds field, evaluated in UTC) at
+    /// which the check is expected to ping, as an alternative to
+    /// `interval_seconds`. The check alerts if it did not ping between an
+    /// occurrence and `grace_seconds` after the following one.
+    pub cron: Option<String>,
+
+    /// Overrides the global `grace_seconds` for this check.
+    pub grace_seconds: Option<i64>,
+
+    /// Overrides the global `recovery_margin_seconds` for this check.
+    pub recovery_margin_seconds: Option<i64>,
+
+    /// Names of the notifiers that alerts for this check are sent to. When
+    /// unset, `default_notifiers` applies.
+    pub notifiers: Option<Vec<String>>,
+
+    /// Number of consecutive missed intervals after which the alert is
+    /// escalated to `escalation_notifiers`.
+    pub escalate_after: Option<u32>,
+
+    /// Names of the notifiers that escalated alerts are sent to. When unset,
+    /// escalated alerts are sent to the check's regular notifiers.
+    pub escalation_notifiers: Option<Vec<String>>,
+
+    /// Arbitrary labels (e.g. `team: payments`) that are included in alerts
+    /// for this check.
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+
+    #[serde(default)]
+    pub severity: Severity,
+
+    /// Name of the group the check belongs to. When all checks of a group
+    /// are failing at the same time, a single alert for the group is sent
+    /// instead of an alert per check.
+    pub group: Option<String>,
+
+    /// IDs of checks this check depends on. While any of them (or their own
+    /// dependencies) is failing, failure alerts of this check are held back,
+    /// as they are most likely caused by the dependency.
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+
+    /// When set, the failure notification is repeated with growing
+    /// intervals until the check recovers or is acknowledged.
+    pub repeat_until_ack: Option<RepeatSettings>,
+
+    /// Text that chat notifiers send instead of their default failure
+    /// message. `{id}` is replaced with the check ID and `{duration}` with
+    /// the time since the last ping, e.g. "3 hours".
+    pub alert_message: Option<String>,
+
+    /// Text that chat notifiers send when the check recovers, with the same
+    /// placeholders; `{duration}` is the time the check was silent. Chat
+    /// notifiers only report recoveries of checks that set it.
+    pub recovery_message: Option<String>,
+
+    /// Whether the first ping only registers the check, e.g. when a job
+    /// pings once at deploy time; the timer is armed by the second ping.
+    #[serde(default)]
+    pub register_first: bool,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct RepeatSettings {
+    /// Time (in seconds) between the first failure notification and its
+    /// first repetition.
+    pub interval_seconds: u64,
+
+    /// Factor by which the time between two repetitions grows.
+    #[serde(default = "default_repeat_backoff_factor")]
+    pub backoff_factor: f64,
+
+    /// Upper bound for the time (in seconds) between two repetitions.
+    pub max_interval_seconds: Option<u64>,
+
+    /// Number of repetitions after which the notification is no longer
+    /// repeated, so that a forgotten check does not alert forever.
+    #[serde(default = "default_repeat_max_repeats")]
+    pub max_repeats: u32,
+}
+
+impl RepeatSettings {
+    /// Returns the time between repetition `n` (counted from zero) and the
+    /// notification before it.
+    pub fn delay(&self, n: u32) -> std::time::Duration {
+        let seconds = self.interval_seconds as f64 * self.backoff_factor.powi(n as i32);
+        let seconds = match self.max_interval_seconds {
+            Some(max) => seconds.min(max as f64),
+            None => seconds,
+        };
+
+        std::time::Duration::from_secs_f64(seconds.min(u32::MAX as f64))
+    }
+}
+
+/// Urgency of a check's alerts, passed on to notifiers. Variants are
+/// ordered from least to most urgent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    #[default]
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+/// Settings that apply to a single check, with all defaults resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedCheck {
+    pub interval_seconds: i64,
+    pub cron: Option<String>,
+    pub grace_seconds: i64,
+    pub recovery_margin_seconds: Option<i64>,
+
+    /// Names of the notifiers that alerts are sent to; `None` means all.
+    pub notifiers: Option<Vec<String>>,
+
+    pub escalate_after: Option<u32>,
+    pub escalation_notifiers: Option<Vec<String>>,
+    pub labels: HashMap<String, String>,
+    pub severity: Severity,
+    pub group: Option<String>,
+    pub depends_on: Vec<String>,
+    pub repeat_until_ack: Option<RepeatSettings>,
+    pub alert_message: Option<String>,
+    pub recovery_message: Option<String>,
+    pub register_first: bool,
+}
+
+impl ResolvedCheck {
+    fn schedule(&self) -> Option<Schedule> {
+        self.cron.as_deref().and_then(|c| Schedule::from_str(c).ok())
+    }
+
+    /// Returns the time from `now` until the first alert should fire for a
+    /// check that last pinged at `last_ping`, or `None` if the check is not
+    /// expected to ping anymore (which can only happen with cron schedules).
+    pub fn delay_until_deadline(&self, last_ping: DateTime<Utc>, now: DateTime<Utc>) -> Option<chrono::Duration> {
+        let deadline = self.deadline(last_ping)?;
+        Some(std::cmp::max(deadline - now, chrono::Duration::zero()))
+    }
+
+    /// Returns the time at which the first alert should fire for a check
+    /// that last pinged at `last_ping`.
+    fn deadline(&self, last_ping: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let expected = match self.schedule() {
+            Some(schedule) => schedule.after(&last_ping).next()?,
+            None => last_ping + chrono::Duration::seconds(self.interval_seconds),
+        };
+
+        Some(expected + chrono::Duration::seconds(self.grace_seconds))
+    }
+
+    /// Whether a ping at `at` recovers an alerted check whose previous ping
+    /// was at `last_ping`. Without a recovery margin, every ping does;
+    /// otherwise only pings that are early enough for the deadline set by
+    /// the previous ping.
+    pub fn recovers_on_ping(&self, last_ping: Option<DateTime<Utc>>, at: DateTime<Utc>) -> bool {
+        let margin = match self.recovery_margin_seconds {
+            Some(margin) => chrono::Duration::seconds(margin),
+            None => return true,
+        };
+
+        last_ping
+            .and_then(|p| self.deadline(p))
+            .is_some_and(|deadline| at <= deadline - margin)
+    }
+
+    /// Time after which another missed ping is counted, once the first
+    /// deadline has passed; for cron schedules, the gap between the two
+    /// occurrences following `now`.
+    pub fn repeat_interval(&self, now: DateTime<Utc>) -> chrono::Duration {
+        let period = self.schedule().and_then(|schedule| {
+            let mut upcoming = schedule.after(&now);
+            let first = upcoming.next()?;
+            let second = upcoming.next()?;
+            Some(second - first)
+        });
+
+        period.unwrap_or_else(|| chrono::Duration::seconds(self.interval_seconds))
+    }
+
+    /// Renders the check's message template for `event`, if it has one.
+    /// `since` is the time of the last ping that `{duration}` refers to.
+    pub fn message(&self, event: AlertEvent, id: &str, since: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<String> {
+        let template = match event {
+            AlertEvent::Failure => self.alert_message.as_ref()?,
+            AlertEvent::Recovery => self.recovery_message.as_ref()?,
+        };
+
+        let duration = since
+            .map(|t| notifier::describe_duration((now - t).num_seconds().max(0)))
+            .unwrap_or_else(|| "an unknown time".to_string());
+
+        Some(template.replace("{id}", id).replace("{duration}", &duration))
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct NotifierSettings {
+    /// Name by which checks can route alerts to this notifier. Defaults to
+    /// the notifier type.
+    pub name: Option<String>,
+
     #[serde(rename="type")]
     pub notifier_type: String,
     pub webhook: Option<WebhookSettings>,
     pub slack: Option<SlackSettings>,
+    pub pagerduty: Option<PagerDutySettings>,
+    pub discord: Option<DiscordSettings>,
+    pub opsgenie: Option<OpsgenieSettings>,
+    pub teams: Option<TeamsSettings>,
+    pub telegram: Option<TelegramSettings>,
+    pub file: Option<FileSettings>,
+    pub mqtt: Option<MqttSettings>,
+    pub twilio: Option<TwilioSettings>,
+    pub matrix: Option<MatrixSettings>,
+    pub gotify: Option<GotifySettings>,
+    pub ntfy: Option<NtfySettings>,
+    pub syslog: Option<SyslogSettings>,
+
+    /// Stops calling the notifier for a while after repeated failures.
+    pub circuit_breaker: Option<CircuitBreakerSettings>,
+}
+
+impl NotifierSettings {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.notifier_type)
+    }
+
+    /// Checks that the section required by the notifier type is present and
+    /// sound, adding a message prefixed with `context` to `problems` for
+    /// each problem found.
+    fn validate(&self, context: &str, problems: &mut Vec<String>) {
+        let mut missing = |section: &str| problems.push(format!("{}: type \"{}\" requires a \"{}\" section", context, self.notifier_type, section));
+
+        let mut urls: Vec<&str> = Vec::new();
+        match self.notifier_type.as_str() {
+            "webhook" => match self.webhook {
+                Some(ref wh) => urls.push(&wh.url),
+                None => missing("webhook"),
+            },
+            "slack" => match self.slack {
+                Some(ref sl) => urls.push(&sl.url),
+                None => missing("slack"),
+            },
+            "discord" => match self.discord {
+                Some(ref d) => urls.push(&d.url),
+                None => missing("discord"),
+            },
+            "teams" => match self.teams {
+                Some(ref t) => urls.push(&t.url),
+                None => missing("teams"),
+            },
+            "pagerduty" => if self.pagerduty.is_none() { missing("pagerduty") },
+            "opsgenie" => if self.opsgenie.is_none() { missing("opsgenie") },
+            "telegram" => if self.telegram.is_none() { missing("telegram") },
+            "file" => if self.file.is_none() { missing("file") },
+            "mqtt" => if self.mqtt.is_none() { missing("mqtt") },
+            "twilio" => if self.twilio.is_none() { missing("twilio") },
+            "matrix" => match self.matrix {
+                Some(ref m) => urls.push(&m.homeserver_url),
+                None => missing("matrix"),
+            },
+            "gotify" => match self.gotify {
+                Some(ref g) => urls.push(&g.server_url),
+                None => missing("gotify"),
+            },
+            "ntfy" => match self.ntfy {
+                Some(ref n) => urls.push(&n.server_url),
+                None => missing("ntfy"),
+            },
+            "syslog" => if self.syslog.is_none() { missing("syslog") },
+            "stdout" | "noop" => {},
+            t => problems.push(format!("{}: unsupported notifier type \"{}\"", context, t)),
+        }
+
+        for url in urls {
+            if let Err(e) = reqwest::Url::parse(url) {
+                problems.push(format!("{}: invalid url \"{}\": {}", context, url, e));
+            }
+        }
+
+        if let Some(ref wh) = self.webhook {
+            if !["get", "post", "put"].contains(&wh.method.to_lowercase().as_str()) {
+                problems.push(format!("{}: unsupported webhook method \"{}\"; must be GET, POST or PUT", context, wh.method));
+            }
+
+            if wh.body_template.as_ref().is_some_and(|t| t.trim().is_empty()) {
+                problems.push(format!("{}: webhook body_template must not be empty", context));
+            }
+
+            if wh.signing_secret.as_ref().is_some_and(|s| s.is_empty()) {
+                problems.push(format!("{}: webhook signing_secret must not be empty", context));
+            }
+
+            if !wh.retry.multiplier.is_finite() || wh.retry.multiplier < 1.0 {
+                problems.push(format!("{}: webhook retry multiplier must be a finite number of at least 1", context));
+            }
+
+            if wh.retry.max_attempts == 0 {
+                problems.push(format!("{}: webhook retry max_attempts must be positive", context));
+            }
+        }
+
+        if let Some(ref pd) = self.pagerduty {
+            if pd.routing_key.trim().is_empty() {
+                problems.push(format!("{}: pagerduty routing_key must not be empty", context));
+            }
+        }
+
+        if let Some(ref og) = self.opsgenie {
+            if og.api_key.trim().is_empty() {
+                problems.push(format!("{}: opsgenie api_key must not be empty", context));
+            }
+
+            if !["us", "eu"].contains(&og.region.to_lowercase().as_str()) {
+                problems.push(format!("{}: unsupported opsgenie region \"{}\"; must be \"us\" or \"eu\"", context, og.region));
+            }
+        }
+
+        if let Some(ref tg) = self.telegram {
+            if tg.bot_token.trim().is_empty() {
+                problems.push(format!("{}: telegram bot_token must not be empty", context));
+            }
+        }
+
+        if let Some(ref f) = self.file {
+            if f.path.trim().is_empty() {
+                problems.push(format!("{}: file path must not be empty", context));
+            }
+        }
+
+        if let Some(ref m) = self.mqtt {
+            if m.host.trim().is_empty() {
+                problems.push(format!("{}: mqtt host must not be empty", context));
+            }
+
+            if m.topic.trim().is_empty() {
+                problems.push(format!("{}: mqtt topic must not be empty", context));
+            }
+
+            if m.qos > 2 {
+                problems.push(format!("{}: unsupported mqtt qos {}; must be 0, 1 or 2", context, m.qos));
+            }
+        }
+
+        if let Some(ref tw) = self.twilio {
+            if tw.account_sid.trim().is_empty() || tw.auth_token.trim().is_empty() {
+                problems.push(format!("{}: twilio account_sid and auth_token must not be empty", context));
+            }
+
+            if tw.to.is_empty() {
+                problems.push(format!("{}: twilio requires at least one number in \"to\"", context));
+            }
+        }
+
+        if let Some(ref m) = self.matrix {
+            if m.access_token.trim().is_empty() {
+                problems.push(format!("{}: matrix access_token must not be empty", context));
+            }
+
+            if m.room_id.trim().is_empty() {
+                problems.push(format!("{}: matrix room_id must not be empty", context));
+            }
+        }
+
+        if let Some(ref g) = self.gotify {
+            if g.token.trim().is_empty() {
+                problems.push(format!("{}: gotify token must not be empty", context));
+            }
+
+            if g.priority > 10 || g.recovery_priority > 10 {
+                problems.push(format!("{}: gotify priorities must be between 0 and 10", context));
+            }
+        }
+
+        if let Some(ref n) = self.ntfy {
+            if n.topic.trim().is_empty() {
+                problems.push(format!("{}: ntfy topic must not be empty", context));
+            }
+
+            if !(1..=5).contains(&n.priority) || !(1..=5).contains(&n.recovery_priority) {
+                problems.push(format!("{}: ntfy priorities must be between 1 and 5", context));
+            }
+
+            match (n.token.is_some(), n.username.is_some(), n.password.is_some()) {
+                (true, false, false) | (false, true, true) | (false, false, false) => {},
+                _ => problems.push(format!("{}: ntfy requires either a token or both username and password", context)),
+            }
+        }
+
+        if let Some(ref sl) = self.syslog {
+            if syslog::Facility::from_str(&sl.facility).is_err() {
+                problems.push(format!("{}: unknown syslog facility \"{}\"", context, sl.facility));
+            }
+
+            if !["udp", "tcp"].contains(&sl.protocol.as_str()) {
+                problems.push(format!("{}: unsupported syslog protocol \"{}\"; must be \"udp\" or \"tcp\"", context, sl.protocol));
+            }
+        }
+
+        if let Some(ref cb) = self.circuit_breaker {
+            if cb.failure_threshold == 0 {
+                problems.push(format!("{}: circuit_breaker failure_threshold must be positive", context));
+            }
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct SlackSettings {
     pub url: String,
+
+    #[serde(default = "default_slack_icon_emoji")]
     pub icon_emoji: String,
-    pub color: String,
+
+    /// HTML color code of the message. When unset, the color depends on
+    /// the severity of the check.
+    pub color: Option<String>,
+
+    /// Overrides the channel configured for the incoming webhook.
+    pub channel: Option<String>,
+
+    /// Overrides the user name configured for the incoming webhook.
+    pub username: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
-pub struct WebhookSettings {
-    pub url: String,
+pub struct PagerDutySettings {
+    /// Integration key of a PagerDuty service using the Events API v2.
+    pub routing_key: String,
 
-    #[serde(default = "default_method")]
-    pub method: String,
+    /// One of "critical", "error", "warning" or "info". When unset, the
+    /// severity of the check is used.
+    pub severity: Option<String>,
 
-    pub body: Option<Value>,
-    pub headers: Option<Vec<(String, String)>>,
+    #[serde(default = "default_pagerduty_source")]
+    pub source: String,
 }
 
-fn default_method() -> String {
-    "POST".to_string()
+#[derive(Debug, Deserialize, Clone)]
+pub struct OpsgenieSettings {
+    /// Key of an Opsgenie API integration.
+    pub api_key: String,
+
+    /// Either "us" or "eu", depending on where the Opsgenie account is
+    /// hosted.
+    #[serde(default = "default_opsgenie_region")]
+    pub region: String,
 }
 
-pub fn retrieve_settings(file: Option<String>) -> Result<Settings, ConfigError> {
-    let mut b = Config::builder();
+#[derive(Debug, Deserialize, Clone)]
+pub struct TeamsSettings {
+    /// URL of a Microsoft Teams incoming webhook.
+    pub url: String,
 
-    if file.is_some() {
-        b = b.add_source(File::with_name(file.unwrap().as_str()));
+    /// Color of the message card, as hex code without leading "#".
+    #[serde(default = "default_teams_theme_color")]
+    pub theme_color: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct TelegramSettings {
+    pub bot_token: String,
+
+    /// ID of the chat to send messages to, or the user name of a channel
+    /// (e.g. "@alerts").
+    pub chat_id: String,
+
+    /// Either "MarkdownV2" or "HTML"; messages are sent as plain text when
+    /// unset.
+    pub parse_mode: Option<String>,
+}
+
+// The bot token is a credential, and must not end up in the log.
+impl std::fmt::Debug for TelegramSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TelegramSettings")
+            .field("bot_token", &"***")
+            .field("chat_id", &self.chat_id)
+            .field("parse_mode", &self.parse_mode)
+            .finish()
     }
+}
 
-    b = b.add_source(Environment::with_prefix("DODEMANSKNOP").separator("_"));
-    b.build()?.try_deserialize()
-}
\ No newline at end of file
+#[derive(Deserialize, Clone)]
+pub struct MatrixSettings {
+    /// Base URL of the homeserver, e.g. "https://matrix.example.com".
+    pub homeserver_url: String,
+
+    /// Access token of the user that posts the messages; the user must have
+    /// joined the room.
+    pub access_token: String,
+
+    /// ID of the room to post to, e.g. "!abcdefg:example.com".
+    pub room_id: String,
+
+    /// Whether messages additionally carry an HTML formatted body.
+    #[serde(default)]
+    pub html: bool,
+}
+
+// The access token is a credential, and must not end up in the log.
+impl std::fmt::Debug for MatrixSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MatrixSettings")
+            .field("homeserver_url", &self.homeserver_url)
+            .field("access_token", &"***")
+            .field("room_id", &self.room_id)
+            .field("html", &self.html)
+            .finish()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SyslogSettings {
+    /// Syslog facility, e.g. "daemon" or "local0".
+    #[serde(default = "default_syslog_facility")]
+    pub facility: String,
+
+    /// Host of a remote syslog collector. When unset, messages are sent to
+    /// the local syslog daemon.
+    pub host: Option<String>,
+
+    #[serde(default = "default_syslog_port")]
+    pub port: u16,
+
+    /// Protocol used to reach the remote collector, "udp" or "tcp".
+    #[serde(default = "default_syslog_protocol")]
+    pub protocol: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct GotifySettings {
+    /// Base URL of the Gotify server, e.g. "https://gotify.example.com".
+    pub server_url: String,
+
+    /// Token of the application that the messages are sent as.
+    pub token: String,
+
+    /// Priority (0 to 10) of failure messages; clients usually only alert
+    /// loudly from priority 8 on.
+    #[serde(default = "default_gotify_priority")]
+    pub priority: u8,
+
+    /// Priority of recovery messages, lower by default so that they do not
+    /// wake anyone up.
+    #[serde(default = "default_gotify_recovery_priority")]
+    pub recovery_priority: u8,
+}
+
+// The application token is a credential, and must not end up in the log.
+impl std::fmt::Debug for GotifySettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GotifySettings")
+            .field("server_url", &self.server_url)
+            .field("token", &"***")
+            .field("priority", &self.priority)
+            .field("recovery_priority", &self.recovery_priority)
+            .finish()
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct NtfySettings {
+    /// Base URL of the ntfy server; defaults to the public "https://ntfy.sh".
+    #[serde(default = "default_ntfy_server_url")]
+    pub server_url: String,
+
+    /// Topic that messages are published to.
+    pub topic: String,
+
+    /// Access token, for servers or topics that require authentication.
+    pub token: Option<String>,
+
+    /// Credentials for basic authentication, as an alternative to a token.
+    pub username: Option<String>,
+    pub password: Option<String>,
+
+    /// Priority (1 to 5) of failure messages.
+    #[serde(default = "default_ntfy_priority")]
+    pub priority: u8,
+
+    /// Priority of recovery messages; the default priority 3 by default.
+    #[serde(default = "default_ntfy_recovery_priority")]
+    pub recovery_priority: u8,
+}
+
+// The token and password are credentials, and must not end up in the log.
+impl std::fmt::Debug for NtfySettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NtfySettings")
+            .field("server_url", &self.server_url)
+            .field("topic", &self.topic)
+            .field("token", &self.token.as_ref().map(|_| "***"))
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .field("priority", &self.priority)
+            .field("recovery_priority", &self.recovery_priority)
+            .finish()
+    }
+}
+
+#[derive(Deserialize, Clone)]
+pub struct TwilioSettings {
+    pub account_sid: String,
+    pub auth_token: String,
+
+    /// Twilio phone number (in E.164 format, e.g. "+15005550006") that
+    /// messages are sent from.
+    pub from: String,
+
+    /// Phone numbers that messages are sent to.
+    pub to: Vec<String>,
+}
+
+// The auth token is a credential, and must not end up in the log.
+impl std::fmt::Debug for TwilioSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TwilioSettings")
+            .field("account_sid", &self.account_sid)
+            .field("auth_token", &"***")
+            .field("from", &self.from)
+            .field("to", &self.to)
+            .finish()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CircuitBreakerSettings {
+    /// Number of consecutive failures after which the circuit opens.
+    #[serde(default = "default_circuit_breaker_failure_threshold")]
+    pub failure_threshold: u32,
+
+    /// Time (in seconds) for which notifications are skipped once the
+    /// circuit is open.
+    #[serde(default = "default_circuit_breaker_cooldown_seconds")]
+    pub cooldown_seconds: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct FileSettings {
+    /// Path of the file that alerts are written to.
+    pub path: String,
+
+    /// Whether to append to an existing file (the default), or to truncate
+    /// it on startup.
+    #[serde(default = "default_file_append")]
+    pub append: bool,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct MqttSettings {
+    /// Host name or address of the MQTT broker.
+    pub host: String,
+
+    #[serde(default = "default_mqtt_port")]
+    pub port: u16,
+
+    /// Client ID to connect with. Defaults to a random ID, so that several
+    /// instances do not disconnect each other.
+    pub client_id: Option<String>,
+
+    pub username: Option<String>,
+    pub password: Option<String>,
+
+    /// Topic to publish to; "{id}", "{event}" and "{severity}" are replaced
+    /// with the respective values of the alert.
+    #[serde(default = "default_mqtt_topic")]
+    pub topic: String,
+
+    /// Topic that heartbeats are published to.
+    #[serde(default = "default_mqtt_heartbeat_topic")]
+    pub heartbeat_topic: String,
+
+    /// Quality of service level of published messages: 0, 1 or 2.
+    #[serde(default = "default_mqtt_qos")]
+    pub qos: u8,
+}
+
+// The password is a credential, and must not end up in the log.
+impl std::fmt::Debug for MqttSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MqttSettings")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("client_id", &self.client_id)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .field("topic", &self.topic)
+            .field("heartbeat_topic", &self.heartbeat_topic)
+            .field("qos", &self.qos)
+            .finish()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DiscordSettings {
+    pub url: String,
+
+    /// Overrides the user name configured for the Discord webhook.
+    pub username: Option<String>,
+
+    /// Overrides the avatar configured for the Discord webhook.
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct WebhookSettings {
+    pub url: String,
+
+    #[serde(default = "default_method")]
+    pub method: String,
+
+    pub body: Option<Value>,
+    pub headers: Option<Vec<(String, String)>>,
+
+    /// Request body with `{id}`, `{timestamp}`, `{event}`, `{severity}` and
+    /// `{payload}` placeholders. When set, this is sent instead of the JSON
+    /// document built from `body`.
+    pub body_template: Option<String>,
+
+    #[serde(default = "default_content_type")]
+    pub content_type: String,
+
+    /// Time (in seconds) after which a single request attempt is aborted.
+    #[serde(default = "default_webhook_timeout_seconds")]
+    pub timeout_seconds: u64,
+
+    #[serde(default)]
+    pub retry: RetrySettings,
+
+    /// When set, each request carries an `X-Signature` header with the
+    /// hex-encoded HMAC-SHA256 of the exact request body, keyed with this
+    /// secret, so that receivers can verify where it came from.
+    pub signing_secret: Option<String>,
+}
+
+// The signing secret is a credential, and must not end up in the log.
+impl std::fmt::Debug for WebhookSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WebhookSettings")
+            .field("url", &self.url)
+            .field("method", &self.method)
+            .field("body", &self.body)
+            .field("headers", &self.headers)
+            .field("body_template", &self.body_template)
+            .field("content_type", &self.content_type)
+            .field("timeout_seconds", &self.timeout_seconds)
+            .field("retry", &self.retry)
+            .field("signing_secret", &self.signing_secret.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+/// Controls how failed webhook requests are retried. Connection errors and
+/// 5xx responses are retried; 4xx responses are not.
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct RetrySettings {
+    /// Maximum number of attempts, including the first one.
+    #[serde(default = "default_retry_max_attempts")]
+    pub max_attempts: u32,
+
+    /// Delay before the first retry, in milliseconds.
+    #[serde(default = "default_retry_base_delay_ms")]
+    pub base_delay_ms: u64,
+
+    /// Factor by which the delay grows with each retry.
+    #[serde(default = "default_retry_multiplier")]
+    pub multiplier: f64,
+
+    /// Upper bound for the total time spent retrying, in seconds.
+    #[serde(default = "default_retry_max_total_seconds")]
+    pub max_total_seconds: u64,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_retry_max_attempts(),
+            base_delay_ms: default_retry_base_delay_ms(),
+            multiplier: default_retry_multiplier(),
+            max_total_seconds: default_retry_max_total_seconds(),
+        }
+    }
+}
+
+fn default_retry_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_base_delay_ms() -> u64 {
+    500
+}
+
+fn default_retry_multiplier() -> f64 {
+    2.0
+}
+
+fn default_retry_max_total_seconds() -> u64 {
+    30
+}
+
+fn default_slack_icon_emoji() -> String {
+    ":rotating_light:".to_string()
+}
+
+fn default_pagerduty_source() -> String {
+    "dodemansknop".to_string()
+}
+
+fn default_webhook_timeout_seconds() -> u64 {
+    10
+}
+
+fn default_teams_theme_color() -> String {
+    "FF0000".to_string()
+}
+
+/// Whether a key of `checks` is a pattern like "worker-*", which applies to
+/// all checks whose ID matches it.
+fn is_pattern(key: &str) -> bool {
+    key.contains('*')
+}
+
+/// Number of characters of a pattern besides `*`.
+fn specificity(pattern: &str) -> usize {
+    pattern.chars().filter(|c| *c != '*').count()
+}
+
+/// Whether `id` matches `pattern`, in which `*` stands for any (possibly
+/// empty) sequence of characters.
+fn glob_matches(pattern: &str, id: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+
+    let mut rest = match id.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let mut parts: Vec<&str> = parts.collect();
+    let last = match parts.pop() {
+        Some(last) => last,
+        // Without any `*`, the pattern has to match exactly.
+        None => return rest.is_empty(),
+    };
+
+    for part in parts {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+fn default_repeat_backoff_factor() -> f64 {
+    2.0
+}
+
+fn default_repeat_max_repeats() -> u32 {
+    10
+}
+
+fn default_gotify_priority() -> u8 {
+    8
+}
+
+fn default_gotify_recovery_priority() -> u8 {
+    3
+}
+
+fn default_ntfy_server_url() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+fn default_ntfy_priority() -> u8 {
+    4
+}
+
+fn default_ntfy_recovery_priority() -> u8 {
+    3
+}
+
+fn default_syslog_facility() -> String {
+    "daemon".to_string()
+}
+
+fn default_syslog_port() -> u16 {
+    514
+}
+
+fn default_syslog_protocol() -> String {
+    "udp".to_string()
+}
+
+fn default_notifier_concurrency() -> usize {
+    4
+}
+
+fn default_notifier_send_timeout_seconds() -> u64 {
+    60
+}
+
+fn default_event_log_size() -> usize {
+    1000
+}
+
+fn default_retry_queue_base_delay_seconds() -> u64 {
+    30
+}
+
+fn default_retry_queue_max_delay_seconds() -> u64 {
+    3600
+}
+
+fn default_retry_queue_max_age_seconds() -> u64 {
+    86400
+}
+
+fn default_circuit_breaker_failure_threshold() -> u32 {
+    5
+}
+
+fn default_circuit_breaker_cooldown_seconds() -> u64 {
+    60
+}
+
+fn default_file_append() -> bool {
+    true
+}
+
+fn default_cors_allowed_methods() -> Vec<String> {
+    ["GET", "POST", "DELETE"].iter().map(|m| m.to_string()).collect()
+}
+
+fn default_cors_allowed_headers() -> Vec<String> {
+    ["authorization", "content-type", "x-request-id"].iter().map(|h| h.to_string()).collect()
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_topic() -> String {
+    "dodemansknop/{id}/{event}".to_string()
+}
+
+fn default_mqtt_heartbeat_topic() -> String {
+    "dodemansknop/heartbeat".to_string()
+}
+
+fn default_mqtt_qos() -> u8 {
+    1
+}
+
+fn default_opsgenie_region() -> String {
+    "us".to_string()
+}
+
+fn default_content_type() -> String {
+    "application/json".to_string()
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+impl Settings {
+    /// Returns the configuration of check `id`. An entry for exactly this ID
+    /// takes precedence over patterns; of several matching patterns, the one
+    /// with the most characters besides `*` wins, and of equally specific
+    /// ones, the alphabetically first.
+    pub fn check_config(&self, id: &str) -> Option<&CheckConfig> {
+        if let Some(cfg) = self.checks.get(id).filter(|_| !is_pattern(id)) {
+            return Some(cfg);
+        }
+
+        self.checks.iter()
+            .filter(|(key, _)| is_pattern(key) && glob_matches(key, id))
+            .min_by(|(a, _), (b, _)| specificity(b).cmp(&specificity(a)).then_with(|| a.cmp(b)))
+            .map(|(_, cfg)| cfg)
+    }
+
+    /// Whether `id` is listed in `checks` itself, rather than only matching
+    /// a pattern.
+    pub fn is_declared(&self, id: &str) -> bool {
+        !is_pattern(id) && self.checks.contains_key(id)
+    }
+
+    /// IDs of the checks listed in `checks`, without patterns.
+    pub fn declared_ids(&self) -> impl Iterator<Item = &String> {
+        self.checks.keys().filter(|key| !is_pattern(key))
+    }
+
+    /// Returns the settings for the check `id`, falling back to the global
+    /// defaults for everything the check does not configure itself.
+    pub fn check(&self, id: &str) -> ResolvedCheck {
+        let cfg = self.check_config(id);
+
+        let interval_seconds = cfg
+            .and_then(|c| c.interval_seconds)
+            .or(self.default_interval_seconds)
+            .or(self.timeout)
+            .unwrap_or(DEFAULT_INTERVAL_SECONDS);
+
+        let notifiers = cfg
+            .and_then(|c| c.notifiers.clone())
+            .or_else(|| self.default_notifiers.clone());
+
+        let escalation_notifiers = cfg
+            .and_then(|c| c.escalation_notifiers.clone())
+            .or_else(|| notifiers.clone());
+
+        let grace_seconds = cfg
+            .and_then(|c| c.grace_seconds)
+            .or(self.grace_seconds)
+            .unwrap_or(0);
+
+        let recovery_margin_seconds = cfg
+            .and_then(|c| c.recovery_margin_seconds)
+            .or(self.recovery_margin_seconds);
+
+        ResolvedCheck {
+            interval_seconds,
+            cron: cfg.and_then(|c| c.cron.clone()),
+            grace_seconds,
+            recovery_margin_seconds,
+            notifiers,
+            escalate_after: cfg.and_then(|c| c.escalate_after),
+            escalation_notifiers,
+            labels: cfg.map(|c| c.labels.clone()).unwrap_or_default(),
+            severity: cfg.map(|c| c.severity).unwrap_or_default(),
+            group: cfg.and_then(|c| c.group.clone()),
+            depends_on: cfg.map(|c| c.depends_on.clone()).unwrap_or_default(),
+            repeat_until_ack: cfg.and_then(|c| c.repeat_until_ack.clone()),
+            alert_message: cfg.and_then(|c| c.alert_message.clone()),
+            recovery_message: cfg.and_then(|c| c.recovery_message.clone()),
+            register_first: cfg.is_some_and(|c| c.register_first),
+        }
+    }
+
+    /// Returns a path of dependencies that leads from check `id` back to
+    /// itself, if there is one.
+    fn dependency_cycle(&self, id: &str) -> Option<Vec<String>> {
+        let mut visited: Vec<&str> = Vec::new();
+        let mut path = vec![id.to_string()];
+
+        fn walk<'a>(settings: &'a Settings, start: &str, current: &str, visited: &mut Vec<&'a str>, path: &mut Vec<String>) -> bool {
+            let deps = match settings.checks.get(current) {
+                Some(c) => &c.depends_on,
+                None => return false,
+            };
+
+            for dep in deps {
+                path.push(dep.clone());
+                if dep == start {
+                    return true;
+                }
+
+                if !visited.contains(&dep.as_str()) {
+                    visited.push(dep);
+                    if walk(settings, start, dep, visited, path) {
+                        return true;
+                    }
+                }
+                path.pop();
+            }
+
+            false
+        }
+
+        if walk(self, id, id, &mut visited, &mut path) {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    /// Describes where the schedule of check `id` comes from, following the
+    /// same order as `check`; used to make misconfigurations visible in the
+    /// log.
+    pub fn interval_origin(&self, id: &str) -> &'static str {
+        let cfg = self.check_config(id);
+
+        if cfg.is_some_and(|c| c.cron.is_some()) {
+            "cron expression of the check"
+        } else if cfg.is_some_and(|c| c.interval_seconds.is_some()) {
+            "interval_seconds of the check"
+        } else if self.default_interval_seconds.is_some() {
+            "default_interval_seconds"
+        } else if self.timeout.is_some() {
+            "deprecated timeout setting"
+        } else {
+            "built-in default"
+        }
+    }
+
+    /// Checks the settings for errors that deserialization cannot catch,
+    /// such as missing notifier sections or references to unknown
+    /// notifiers. All problems are reported at once.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let mut problems = Vec::new();
+
+        let mut names: Vec<&str> = Vec::new();
+        for (i, notifier) in self.notifiers.iter().enumerate() {
+            let context = format!("notifiers[{}] ({})", i, notifier.name());
+
+            if names.contains(&notifier.name()) {
+                problems.push(format!("{}: duplicate notifier name; set a unique \"name\"", context));
+            }
+            names.push(notifier.name());
+
+            notifier.validate(&context, &mut problems);
+        }
+
+        let routes = self.default_notifiers.iter().map(|n| ("default_notifiers".to_string(), n))
+            .chain(self.heartbeat.iter().flat_map(|h| h.notifiers.iter()).map(|n| ("heartbeat.notifiers".to_string(), n)))
+            .chain(self.checks.iter().flat_map(|(id, c)| {
+                c.notifiers.iter().map(move |n| (format!("checks.{}.notifiers", id), n))
+                    .chain(c.escalation_notifiers.iter().map(move |n| (format!("checks.{}.escalation_notifiers", id), n)))
+            }));
+
+        for (context, route) in routes {
+            for name in route.iter().filter(|n| !names.contains(&n.as_str())) {
+                problems.push(format!("{}: unknown notifier \"{}\"", context, name));
+            }
+        }
+
+        for (id, check) in self.checks.iter() {
+            if let Some(ref expr) = check.cron {
+                if check.interval_seconds.is_some() {
+                    problems.push(format!("checks.{}: only one of interval_seconds and cron may be set", id));
+                }
+
+                if let Err(e) = Schedule::from_str(expr) {
+                    problems.push(format!("checks.{}: invalid cron expression '{}': {}", id, expr, e));
+                }
+            }
+
+            if check.interval_seconds.is_some_and(|i| i <= 0) {
+                problems.push(format!("checks.{}: interval_seconds must be positive", id));
+            }
+
+            if let Some(ref group) = check.group {
+                if group.trim().is_empty() {
+                    problems.push(format!("checks.{}: group must not be empty", id));
+                }
+
+                // Group alerts carry the group name as check ID.
+                if self.checks.contains_key(group) {
+                    problems.push(format!("checks.{}: group \"{}\" has the same name as a check", id, group));
+                }
+            }
+
+            if check.grace_seconds.is_some_and(|g| g < 0) {
+                problems.push(format!("checks.{}: grace_seconds must not be negative", id));
+            }
+
+            if check.recovery_margin_seconds.is_some_and(|m| m < 0) {
+                problems.push(format!("checks.{}: recovery_margin_seconds must not be negative", id));
+            }
+
+            if let Some(ref repeat) = check.repeat_until_ack {
+                if repeat.interval_seconds == 0 {
+                    problems.push(format!("checks.{}: repeat_until_ack interval_seconds must be positive", id));
+                }
+
+                if !(repeat.backoff_factor >= 1.0 && repeat.backoff_factor.is_finite()) {
+                    problems.push(format!("checks.{}: repeat_until_ack backoff_factor must be at least 1", id));
+                }
+
+                if repeat.max_interval_seconds.is_some_and(|max| max < repeat.interval_seconds) {
+                    problems.push(format!("checks.{}: repeat_until_ack max_interval_seconds must not be less than interval_seconds", id));
+                }
+
+                if repeat.max_repeats == 0 {
+                    problems.push(format!("checks.{}: repeat_until_ack max_repeats must be positive", id));
+                }
+            }
+
+            // Each cycle is reported once, for its smallest check ID.
+            if let Some(cycle) = self.dependency_cycle(id) {
+                if cycle.iter().all(|other| id <= other) {
+                    problems.push(format!("checks.{}: dependency cycle {}", id, cycle.join(" -> ")));
+                }
+            }
+        }
+
+        for (i, window) in self.maintenance_windows.iter().enumerate() {
+            if let Err(e) = window.window() {
+                problems.push(format!("maintenance_windows[{}]: invalid cron expression '{}': {}", i, window.cron, e));
+            }
+
+            if window.duration_minutes == 0 {
+                problems.push(format!("maintenance_windows[{}]: duration_minutes must be positive", i));
+            }
+        }
+
+        if let Some(ref heartbeat) = self.heartbeat {
+            if heartbeat.interval_seconds == 0 {
+                problems.push("heartbeat: interval_seconds must be positive".to_string());
+            }
+
+            for name in heartbeat.notifiers.iter().flatten() {
+                let unsupported = self.notifiers.iter()
+                    .find(|n| n.name() == name)
+                    .filter(|n| !HEARTBEAT_NOTIFIER_TYPES.contains(&n.notifier_type.as_str()));
+
+                if let Some(n) = unsupported {
+                    problems.push(format!("heartbeat.notifiers: notifier \"{}\" of type \"{}\" does not support heartbeats", name, n.notifier_type));
+                }
+            }
+        }
+
+        if self.notifier_send_timeout_seconds == 0 {
+            problems.push("notifier_send_timeout_seconds must be positive".to_string());
+        }
+
+        if self.notifier_concurrency == 0 {
+            problems.push("notifier_concurrency must be positive".to_string());
+        }
+
+        if let Some(ref queue) = self.retry_queue {
+            if queue.path.trim().is_empty() {
+                problems.push("retry_queue: path must not be empty".to_string());
+            }
+
+            if queue.base_delay_seconds == 0 {
+                problems.push("retry_queue: base_delay_seconds must be positive".to_string());
+            }
+        }
+
+        if self.default_interval_seconds.or(self.timeout).is_some_and(|i| i <= 0) {
+            problems.push("default_interval_seconds must be positive".to_string());
+        }
+
+        if self.grace_seconds.is_some_and(|g| g < 0) {
+            problems.push("grace_seconds must not be negative".to_string());
+        }
+
+        if self.recovery_margin_seconds.is_some_and(|m| m < 0) {
+            problems.push("recovery_margin_seconds must not be negative".to_string());
+        }
+
+        if let Some(limit) = self.server.ping_rate_limit {
+            if limit.is_nan() || limit <= 0.0 {
+                problems.push("server.ping_rate_limit must be positive".to_string());
+            }
+        }
+
+        if self.log_level.is_some() && self.log.level.is_some() {
+            problems.push("log_level and log.level must not both be set".to_string());
+        }
+
+        if self.state_file.is_some() && self.state_backend.is_some() {
+            problems.push("state_file and state_backend must not both be set".to_string());
+        }
+
+        if let Some(StateBackendSettings::Json { ref path } | StateBackendSettings::Sqlite { ref path }) = self.state_backend {
+            if path.trim().is_empty() {
+                problems.push("state_backend path must not be empty".to_string());
+            }
+        }
+
+        match self.server.check_id_regex() {
+            Ok(pattern) => {
+                let mut ids: Vec<&String> = self.declared_ids().filter(|id| !pattern.is_match(id)).collect();
+                ids.sort();
+                for id in ids {
+                    problems.push(format!("checks.{}: id does not match server.check_id_pattern, so it cannot be pinged", id));
+                }
+            },
+            Err(e) => problems.push(format!("server.check_id_pattern is not a valid regular expression: {}", e)),
+        }
+
+        if self.ping_queue_capacity.is_some() && self.server.ping_queue_capacity.is_some() {
+            problems.push("ping_queue_capacity and server.ping_queue_capacity must not both be set".to_string());
+        }
+
+        if self.ping_queue_capacity() == 0 {
+            problems.push("ping_queue_capacity must be positive".to_string());
+        }
+
+        if self.server.max_checks == Some(0) {
+            problems.push("server.max_checks must be positive".to_string());
+        }
+
+        let mut addrs: Vec<SocketAddr> = Vec::new();
+        for (i, listener) in self.server.listeners.iter().enumerate() {
+            match parse_socket_addr(&listener.bind_address, listener.port) {
+                Ok(addr) if addrs.contains(&addr) => problems.push(format!("server.listeners[{}]: {} is used by another listener", i, addr)),
+                Ok(addr) => addrs.push(addr),
+                Err(e) => problems.push(format!("server.listeners[{}]: {}", i, e)),
+            }
+        }
+
+        if !self.server.listeners.is_empty() && self.server.listeners.iter().all(|l| l.routes == RouteSet::Admin) {
+            problems.push("server.listeners: no listener serves pings; add one with routes \"ping\" or \"all\"".to_string());
+        }
+
+        if let Some(ref tls) = self.tls {
+            match (&tls.cert_path, &tls.key_path) {
+                (Some(cert), Some(key)) => {
+                    for path in [cert, key] {
+                        if let Err(e) = std::fs::metadata(path) {
+                            problems.push(format!("tls: cannot access {}: {}", path, e));
+                        }
+                    }
+                },
+                _ => problems.push("tls: both cert_path and key_path must be set".to_string()),
+            }
+        }
+
+        if let Some(ref cors) = self.cors {
+            for origin in cors.allowed_origins.iter().filter(|o| *o != "*") {
+                // An origin is a URL that consists of scheme, host and port
+                // only.
+                let valid = reqwest::Url::parse(origin)
+                    .is_ok_and(|url| url.origin().is_tuple() && url.origin().ascii_serialization() == *origin);
+
+                if !valid {
+                    problems.push(format!("cors: invalid origin \"{}\"; must look like \"https://example.com\"", origin));
+                }
+            }
+
+            for method in cors.allowed_methods.iter() {
+                if reqwest::Method::from_bytes(method.as_bytes()).is_err() {
+                    problems.push(format!("cors: invalid method \"{}\"", method));
+                }
+            }
+
+            for header in cors.allowed_headers.iter() {
+                if reqwest::header::HeaderName::from_bytes(header.as_bytes()).is_err() {
+                    problems.push(format!("cors: invalid header name \"{}\"", header));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Returns the CORS settings if CORS is enabled, i.e. if any origins are
+    /// allowed.
+    pub fn cors(&self) -> Option<&CorsSettings> {
+        self.cors.as_ref().filter(|c| !c.allowed_origins.is_empty())
+    }
+
+    /// Returns the certificate and key paths if TLS is enabled.
+    pub fn tls_paths(&self) -> Option<(String, String)> {
+        let tls = self.tls.as_ref()?;
+        Some((tls.cert_path.clone()?, tls.key_path.clone()?))
+    }
+
+    /// Returns the configured state backend, taking `state_file` into
+    /// account.
+    pub fn state_backend(&self) -> Option<StateBackendSettings> {
+        match (&self.state_backend, &self.state_file) {
+            (Some(backend), _) => Some(backend.clone()),
+            (None, Some(path)) => Some(StateBackendSettings::Json { path: path.clone() }),
+            (None, None) => None,
+        }
+    }
+
+    /// Describes the deprecated settings in use, which still work but should
+    /// be replaced.
+    pub fn deprecations(&self) -> Vec<String> {
+        let mut deprecations = Vec::new();
+
+        if self.log.level.is_some() {
+            deprecations.push("log.level is deprecated; set the top-level log_level instead".to_string());
+        }
+
+        if self.server.ping_queue_capacity.is_some() {
+            deprecations.push("server.ping_queue_capacity is deprecated; set the top-level ping_queue_capacity instead".to_string());
+        }
+
+        deprecations
+    }
+
+    /// The capacity of the ping queue: `ping_queue_capacity`, or the
+    /// deprecated `server.ping_queue_capacity`. Setting both is rejected by
+    /// [`Settings::validate`].
+    pub fn ping_queue_capacity(&self) -> usize {
+        self.ping_queue_capacity.or(self.server.ping_queue_capacity).unwrap_or_else(default_ping_queue_capacity)
+    }
+
+    /// The default log level: `log_level`, or the deprecated `log.level`.
+    /// Setting both is rejected by [`Settings::validate`]. `RUST_LOG` and
+    /// `--log-level` take precedence over either.
+    pub fn log_level(&self) -> Option<LogLevel> {
+        self.log_level.or(self.log.level)
+    }
+
+    pub fn auth_token(&self) -> Option<String> {
+        self.auth.as_ref().and_then(|a| a.token.clone())
+    }
+
+    pub fn shutdown_timeout_seconds(&self) -> u64 {
+        self.shutdown_timeout_seconds.unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECONDS)
+    }
+}
+
+/// Determines the format of a configuration file from its extension.
+fn file_format(file: &str) -> Result<FileFormat, ConfigError> {
+    let ext = Path::new(file).extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase());
+
+    match ext.as_deref() {
+        Some("json") => Ok(FileFormat::Json),
+        Some("toml") => Ok(FileFormat::Toml),
+        Some("yaml") | Some("yml") => Ok(FileFormat::Yaml),
+        Some(e) => Err(ConfigError::Message(format!("unsupported configuration file extension '.{}' of {}; expected .json, .toml, .yaml or .yml", e, file))),
+        None => Err(ConfigError::Message(format!("configuration file {} has no extension; expected .json, .toml, .yaml or .yml", file))),
+    }
+}
+
+/// Lists the configuration fragments in directory `dir`, sorted by file
+/// name. Hidden files and files without a supported extension are skipped.
+fn fragment_files(dir: &str) -> Result<Vec<String>, ConfigError> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| ConfigError::Message(format!("could not read configuration directory {}: {}", dir, e)))?;
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry
+            .map_err(|e| ConfigError::Message(format!("could not read configuration directory {}: {}", dir, e)))?
+            .path();
+
+        let hidden = path.file_name().and_then(|n| n.to_str()).is_none_or(|n| n.starts_with('.'));
+        if hidden || !path.is_file() {
+            continue;
+        }
+
+        let file = path.to_string_lossy().into_owned();
+        if file_format(&file).is_ok() {
+            files.push(file);
+        }
+    }
+
+    if files.is_empty() {
+        return Err(ConfigError::Message(format!("configuration directory {} contains no .json, .toml, .yaml or .yml files", dir)));
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Parses each fragment on its own, so that all broken fragments are
+/// reported at once, and each along with its file name.
+fn check_fragments(files: &[String]) -> Result<(), ConfigError> {
+    let problems: Vec<String> = files.iter()
+        .filter_map(|file| {
+            let format = match file_format(file) {
+                Ok(format) => format,
+                Err(e) => return Some(e.to_string()),
+            };
+
+            Config::builder()
+                .add_source(File::new(file, format))
+                .build()
+                .err()
+                .map(|e| e.to_string())
+        })
+        .collect();
+
+    match problems.is_empty() {
+        true => Ok(()),
+        false => Err(ConfigError::Message(format!("could not load configuration fragments: {}", problems.join("; ")))),
+    }
+}
+
+/// Loads the settings from `file`, or from all fragments in `file` if it is
+/// a directory, and from the environment. Fragments are merged in the order
+/// of their file names: for keys set by several fragments, the one sorting
+/// last wins. Tables such as `checks` are merged key by key, while lists
+/// such as `notifiers` are replaced as a whole.
+pub fn retrieve_settings(file: Option<String>) -> Result<Settings, ConfigError> {
+    let mut b = Config::builder();
+
+    if let Some(file) = file {
+        let files = if Path::new(&file).is_dir() {
+            let files = fragment_files(&file)?;
+            check_fragments(&files)?;
+            files
+        } else {
+            vec![file]
+        };
+
+        for file in files {
+            b = b.add_source(File::new(file.as_str(), file_format(&file)?));
+        }
+    }
+
+    b = b.add_source(Environment::with_prefix("DODEMANSKNOP").separator("_"));
+
+    let mut root: config::Value = b.build()?.try_deserialize()?;
+    expand_env_vars(&mut root, "")?;
+    root.try_deserialize()
+}
+
+/// Replaces `${NAME}` references in all string values below `value` with the
+/// contents of the environment variable `NAME`, so that secrets can be kept
+/// out of the configuration file. `path` names `value` in error messages.
+fn expand_env_vars(value: &mut config::Value, path: &str) -> Result<(), ConfigError> {
+    match value.kind {
+        ValueKind::String(ref mut s) => *s = expand_env_refs(s, path)?,
+        ValueKind::Table(ref mut table) => {
+            for (key, v) in table.iter_mut() {
+                let path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                expand_env_vars(v, &path)?;
+            }
+        },
+        ValueKind::Array(ref mut array) => {
+            for (i, v) in array.iter_mut().enumerate() {
+                expand_env_vars(v, &format!("{}[{}]", path, i))?;
+            }
+        },
+        _ => {},
+    }
+
+    Ok(())
+}
+
+fn expand_env_refs(s: &str, path: &str) -> Result<String, ConfigError> {
+    let mut expanded = String::new();
+    let mut rest = s;
+
+    while let Some(start) = rest.find("${") {
+        let end = match rest[start + 2..].find('}') {
+            Some(len) => start + 2 + len,
+            None => return Err(ConfigError::Message(format!("unterminated environment variable reference in {}", path))),
+        };
+
+        let name = &rest[start + 2..end];
+        let contents = std::env::var(name)
+            .map_err(|_| ConfigError::Message(format!("environment variable {} referenced by {} is not set", name, path)))?;
+
+        expanded.push_str(&rest[..start]);
+        expanded.push_str(&contents);
+        rest = &rest[end + 1..];
+    }
+
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Parses `yaml` as configuration file, with a single no-op notifier.
+    pub(crate) fn settings(yaml: &str) -> Settings {
+        let yaml = format!("notifiers: [{{ type: noop }}]\n{}", yaml);
+
+        Config::builder()
+            .add_source(File::from_str(&yaml, FileFormat::Yaml))
+            .build()
+            .and_then(|c| c.try_deserialize())
+            .unwrap()
+    }
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z").unwrap().with_timezone(&Utc) + chrono::Duration::seconds(seconds)
+    }
+
+    #[test]
+    fn recovers_on_any_ping_without_margin() {
+        let check = settings("checks: { a: { interval_seconds: 60 } }").check("a");
+
+        assert!(check.recovers_on_ping(Some(at(0)), at(60)));
+        assert!(check.recovers_on_ping(Some(at(0)), at(120)));
+        assert!(check.recovers_on_ping(None, at(0)));
+    }
+
+    #[test]
+    fn recovers_only_on_pings_before_the_margin() {
+        let check = settings("checks: { a: { interval_seconds: 60, recovery_margin_seconds: 10 } }").check("a");
+
+        assert!(check.recovers_on_ping(Some(at(0)), at(49)));
+        assert!(check.recovers_on_ping(Some(at(0)), at(50)), "a ping exactly at the margin recovers");
+        assert!(!check.recovers_on_ping(Some(at(0)), at(51)), "a ping just inside the margin does not recover");
+        assert!(!check.recovers_on_ping(Some(at(0)), at(55)));
+        assert!(!check.recovers_on_ping(Some(at(0)), at(60)), "a ping exactly at the deadline does not recover");
+        assert!(!check.recovers_on_ping(Some(at(0)), at(61)), "a ping just after the deadline does not recover");
+    }
+
+    #[test]
+    fn recovery_margin_counts_from_the_deadline_including_grace() {
+        let check = settings("checks: { a: { interval_seconds: 60, grace_seconds: 5, recovery_margin_seconds: 10 } }").check("a");
+
+        assert!(check.recovers_on_ping(Some(at(0)), at(55)));
+        assert!(!check.recovers_on_ping(Some(at(0)), at(56)));
+    }
+
+    #[test]
+    fn recovery_margin_requires_a_previous_ping() {
+        let check = settings("checks: { a: { interval_seconds: 60, recovery_margin_seconds: 10 } }").check("a");
+
+        assert!(!check.recovers_on_ping(None, at(0)));
+    }
+
+    #[test]
+    fn pings_within_the_margin_keep_a_check_from_flapping() {
+        let check = settings("checks: { a: { interval_seconds: 60, recovery_margin_seconds: 10 } }").check("a");
+
+        // A job that pings a second before each deadline keeps its timer
+        // armed, but never recovers; a job that catches up does.
+        let late_pings = [59, 118, 177];
+        for pair in late_pings.windows(2) {
+            assert!(!check.recovers_on_ping(Some(at(pair[0])), at(pair[1])));
+        }
+
+        assert!(check.recovers_on_ping(Some(at(177)), at(200)));
+    }
+
+    #[test]
+    fn cron_repeat_interval_follows_the_schedule_after_now() {
+        // 2026-01-01 is a Thursday.
+        let check = settings("checks: { a: { cron: '0 0 9 * * Mon-Fri' } }").check("a");
+
+        assert_eq!(check.repeat_interval(at(0)), chrono::Duration::days(1));
+        assert_eq!(check.repeat_interval(at(10 * 3600)), chrono::Duration::days(3));
+    }
+
+    #[test]
+    fn server_listens_on_localhost_by_default() {
+        assert_eq!(settings("").server.listen_addrs(), Ok(vec![("127.0.0.1:3030".parse().unwrap(), RouteSet::All)]));
+    }
+
+    /// Interval of the configuration that `check_config` picks for `id`.
+    fn matched_interval(settings: &Settings, id: &str) -> Option<i64> {
+        settings.check_config(id).and_then(|c| c.interval_seconds)
+    }
+
+    #[test]
+    fn exact_check_ids_take_precedence_over_patterns() {
+        let settings = settings("checks: { 'backup-*': { interval_seconds: 10 }, 'backup-db': { interval_seconds: 20 } }");
+
+        assert_eq!(matched_interval(&settings, "backup-db"), Some(20));
+        assert_eq!(matched_interval(&settings, "backup-web"), Some(10));
+        assert_eq!(matched_interval(&settings, "cleanup"), None);
+    }
+
+    #[test]
+    fn more_specific_patterns_take_precedence() {
+        let settings = settings("checks: { '*': { interval_seconds: 10 }, 'backup-*': { interval_seconds: 20 }, 'backup-*-eu': { interval_seconds: 30 } }");
+
+        assert_eq!(matched_interval(&settings, "backup-db-eu"), Some(30));
+        assert_eq!(matched_interval(&settings, "backup-db-us"), Some(20));
+        assert_eq!(matched_interval(&settings, "cleanup"), Some(10));
+    }
+
+    #[test]
+    fn equally_specific_patterns_are_tried_alphabetically() {
+        let settings = settings("checks: { 'db-*': { interval_seconds: 10 }, '*-db': { interval_seconds: 20 } }");
+
+        // "*-db" sorts before "db-*", as "*" sorts before letters.
+        assert_eq!(matched_interval(&settings, "db-db"), Some(20));
+        assert_eq!(matched_interval(&settings, "db-web"), Some(10));
+    }
+
+    #[test]
+    fn env_refs_are_replaced_with_the_variables() {
+        std::env::set_var("CONFIG_TEST_TOKEN", "s3cr3t");
+
+        assert_eq!(expand_env_refs("https://hooks/${CONFIG_TEST_TOKEN}/${CONFIG_TEST_TOKEN}", "url").unwrap(), "https://hooks/s3cr3t/s3cr3t");
+        assert_eq!(expand_env_refs("costs $5 {or} $ {more}", "url").unwrap(), "costs $5 {or} $ {more}");
+    }
+
+    #[test]
+    fn unset_env_refs_name_the_variable_and_setting() {
+        let err = expand_env_refs("${CONFIG_TEST_UNSET}", "notifiers[0].slack.url").unwrap_err();
+        assert_eq!(err.to_string(), "environment variable CONFIG_TEST_UNSET referenced by notifiers[0].slack.url is not set");
+    }
+
+    #[test]
+    fn unterminated_env_refs_are_rejected() {
+        let err = expand_env_refs("https://hooks/${TOKEN", "notifiers[0].slack.url").unwrap_err();
+        assert_eq!(err.to_string(), "unterminated environment variable reference in notifiers[0].slack.url");
+    }
+
+    #[test]
+    fn env_refs_are_expanded_when_loading_the_configuration() {
+        std::env::set_var("CONFIG_TEST_MESSAGE", "backup failed");
+
+        let path = std::env::temp_dir().join(format!("dodemansknop-{}.yaml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "notifiers: [{ type: noop }]\nchecks: { a: { alert_message: '${CONFIG_TEST_MESSAGE}' } }").unwrap();
+        let settings = retrieve_settings(Some(path.to_str().unwrap().to_string()));
+        let _ = std::fs::remove_file(&path);
+
+        let settings = settings.unwrap();
+        assert_eq!(settings.check_config("a").unwrap().alert_message.as_deref(), Some("backup failed"));
+    }
+
+    /// Creates a configuration directory in the temp directory with the
+    /// given files and contents.
+    fn fragment_dir(files: &[(&str, &str)]) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("dodemansknop-{}.d", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+
+        for (name, contents) in files {
+            std::fs::write(dir.join(name), contents).unwrap();
+        }
+
+        dir
+    }
+
+    fn retrieve_dir(dir: &std::path::Path) -> Result<Settings, ConfigError> {
+        let settings = retrieve_settings(Some(dir.to_str().unwrap().to_string()));
+        let _ = std::fs::remove_dir_all(dir);
+        settings
+    }
+
+    #[test]
+    fn later_fragments_override_earlier_ones() {
+        let dir = fragment_dir(&[
+            ("20-team.yaml", "default_interval_seconds: 60\nchecks: { a: { interval_seconds: 20 }, b: { interval_seconds: 30 } }"),
+            ("10-base.yaml", "notifiers: [{ type: noop }]\ndefault_interval_seconds: 30\nchecks: { a: { interval_seconds: 10, grace_seconds: 5 } }"),
+            (".20-editor-backup.yaml", "default_interval_seconds: ["),
+            ("README.md", "not a fragment"),
+        ]);
+
+        let settings = retrieve_dir(&dir).unwrap();
+
+        assert_eq!(settings.default_interval_seconds, Some(60));
+        assert_eq!(settings.check("a").interval_seconds, 20);
+        assert_eq!(settings.check("a").grace_seconds, 5);
+        assert_eq!(settings.check("b").interval_seconds, 30);
+    }
+
+    #[test]
+    fn broken_fragments_are_reported_with_their_file_names() {
+        let dir = fragment_dir(&[
+            ("10-base.yaml", "notifiers: [{ type: noop }]"),
+            ("20-broken.yaml", "checks: ["),
+            ("30-broken.json", "{ \"checks\": "),
+        ]);
+
+        let err = retrieve_dir(&dir).unwrap_err().to_string();
+
+        assert!(err.starts_with("could not load configuration fragments: "), "{}", err);
+        assert!(err.contains("20-broken.yaml"), "{}", err);
+        assert!(err.contains("30-broken.json"), "{}", err);
+        assert!(!err.contains("10-base.yaml"), "{}", err);
+    }
+
+    #[test]
+    fn maintenance_windows_are_active_for_their_duration() {
+        // 2026-01-01 is a Thursday.
+        let window = settings("maintenance_windows: [{ cron: '0 0 2 * * Thu', duration_minutes: 30 }]").maintenance_windows[0].window().unwrap();
+
+        assert!(!window.is_active(at(2 * 3600 - 1)));
+        assert!(window.is_active(at(2 * 3600)));
+        assert!(window.is_active(at(2 * 3600 + 29 * 60)));
+        assert!(!window.is_active(at(2 * 3600 + 31 * 60)));
+    }
+
+    #[test]
+    fn invalid_maintenance_window_cron_is_a_config_problem() {
+        let problems = settings("maintenance_windows: [{ cron: 'at night', duration_minutes: 30 }]").validate().unwrap_err();
+        assert!(problems.iter().any(|p| p.starts_with("maintenance_windows[0]: invalid cron expression 'at night'")), "{:?}", problems);
+    }
+
+    #[test]
+    fn default_check_id_pattern_accepts_plain_ids() {
+        let pattern = ServerSettings::default().check_id_regex().unwrap();
+
+        for id in ["backup", "nightly-backup", "db_1.example:5432", "A", &"x".repeat(128)] {
+            assert!(pattern.is_match(id), "{:?} should be accepted", id);
+        }
+    }
+
+    #[test]
+    fn default_check_id_pattern_rejects_path_breaking_ids() {
+        let pattern = ServerSettings::default().check_id_regex().unwrap();
+
+        for id in ["", "a/b", "../etc", "a b", "a\nb", "a%2Fb", "caf\u{e9}", &"x".repeat(129)] {
+            assert!(!pattern.is_match(id), "{:?} should be rejected", id);
+        }
+    }
+
+    #[test]
+    fn check_id_pattern_has_to_match_the_whole_id() {
+        let server = ServerSettings { check_id_pattern: "[a-z]+|[0-9]+".to_string(), ..Default::default() };
+        let pattern = server.check_id_regex().unwrap();
+
+        assert!(pattern.is_match("abc"));
+        assert!(pattern.is_match("123"));
+        assert!(!pattern.is_match("abc123"), "the alternation must be anchored as a whole");
+        assert!(!pattern.is_match("abc/"), "partial matches must be rejected");
+        assert!(!pattern.is_match("/123"), "partial matches must be rejected");
+    }
+
+    #[test]
+    fn invalid_check_id_pattern_is_a_config_problem() {
+        let settings = settings("server: { check_id_pattern: \"[a-z\" }");
+
+        let problems = settings.validate().unwrap_err();
+        assert!(problems.iter().any(|p| p.starts_with("server.check_id_pattern is not a valid regular expression")), "{:?}", problems);
+    }
+
+    #[test]
+    fn declared_check_ids_have_to_match_the_pattern() {
+        let settings = settings("server: { check_id_pattern: \"[a-z]+\" }\nchecks: { backup1: { interval_seconds: 60 } }");
+
+        let problems = settings.validate().unwrap_err();
+        assert!(problems.contains(&"checks.backup1: id does not match server.check_id_pattern, so it cannot be pinged".to_string()), "{:?}", problems);
+    }
+
+    #[test]
+    fn log_level_is_set_at_the_top_level() {
+        assert_eq!(settings("log_level: debug").log_level(), Some(LogLevel::Debug));
+        assert!(settings("log_level: debug").deprecations().is_empty());
+        assert_eq!(settings("").log_level(), None);
+    }
+
+    #[test]
+    fn log_section_level_is_deprecated() {
+        let old = settings("log: { level: warn }");
+        assert_eq!(old.log_level(), Some(LogLevel::Warn));
+        assert_eq!(old.deprecations(), vec!["log.level is deprecated; set the top-level log_level instead".to_string()]);
+
+        let problems = settings("log_level: debug\nlog: { level: warn }").validate().unwrap_err();
+        assert!(problems.contains(&"log_level and log.level must not both be set".to_string()), "{:?}", problems);
+    }
+
+    #[test]
+    fn unknown_log_levels_are_rejected() {
+        let parsed = Config::builder()
+            .add_source(File::from_str("notifiers: [{ type: noop }]\nlog_level: verbose", FileFormat::Yaml))
+            .build()
+            .and_then(|c| c.try_deserialize::<Settings>());
+
+        assert!(parsed.is_err());
+    }
+
+    #[test]
+    fn ping_queue_capacity_is_set_at_the_top_level() {
+        assert_eq!(settings("").ping_queue_capacity(), 32);
+        assert_eq!(settings("ping_queue_capacity: 128").ping_queue_capacity(), 128);
+        assert!(settings("ping_queue_capacity: 128").deprecations().is_empty());
+
+        let problems = settings("ping_queue_capacity: 0").validate().unwrap_err();
+        assert!(problems.contains(&"ping_queue_capacity must be positive".to_string()), "{:?}", problems);
+    }
+
+    #[test]
+    fn server_ping_queue_capacity_is_deprecated() {
+        let old = settings("server: { ping_queue_capacity: 64 }");
+        assert_eq!(old.ping_queue_capacity(), 64);
+        assert_eq!(old.deprecations().len(), 1);
+
+        let problems = settings("ping_queue_capacity: 128\nserver: { ping_queue_capacity: 64 }").validate().unwrap_err();
+        assert!(problems.contains(&"ping_queue_capacity and server.ping_queue_capacity must not both be set".to_string()), "{:?}", problems);
+    }
+}
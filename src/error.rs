@@ -0,0 +1,67 @@
+use reqwest::StatusCode;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DodemansknopError {
+    /// The configuration of a component is invalid; retrying will not help.
+    #[error("invalid configuration: {0}")]
+    Config(String),
+
+    /// A request could not be completed, or failed with a response that
+    /// indicates a temporary problem (5xx, 429). Retrying may help.
+    #[error("{0}")]
+    Transport(String),
+
+    /// A request was rejected by its receiver (e.g. 4xx); retrying will not
+    /// help.
+    #[error("{0}")]
+    Rejected(String),
+
+    /// An internal channel was closed, usually because of a shutdown.
+    #[error("channel closed: {0}")]
+    Channel(String),
+
+    /// Several notifiers failed; each error is paired with the name of the
+    /// notifier that returned it.
+    #[error("{}", format_notifier_errors(.0))]
+    Notifiers(Vec<(String, DodemansknopError)>),
+}
+
+impl DodemansknopError {
+    /// Builds the error for an unsuccessful response of `target`.
+    pub fn from_status(target: &str, status: StatusCode, text: &str) -> Self {
+        let message = if text.is_empty() {
+            format!("{} responded with {}", target, status)
+        } else {
+            format!("{} responded with {}: {}", target, status, text)
+        };
+
+        if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+            DodemansknopError::Transport(message)
+        } else {
+            DodemansknopError::Rejected(message)
+        }
+    }
+
+    /// Whether repeating the failed operation may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            DodemansknopError::Transport(_) => true,
+            DodemansknopError::Notifiers(errors) => errors.iter().any(|(_, e)| e.is_retryable()),
+            _ => false,
+        }
+    }
+}
+
+impl<T> From<tokio::sync::mpsc::error::SendError<T>> for DodemansknopError {
+    fn from(_: tokio::sync::mpsc::error::SendError<T>) -> Self {
+        DodemansknopError::Channel("receiver has been dropped".to_string())
+    }
+}
+
+fn format_notifier_errors(errors: &[(String, DodemansknopError)]) -> String {
+    errors.iter()
+        .map(|(name, e)| format!("{}: {}", name, e))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
@@ -0,0 +1,78 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Kind of an entry in the event log.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Ping,
+    Failure,
+    Escalation,
+    Repeat,
+    Recovery,
+    Pause,
+    Resume,
+    Acknowledge,
+    Delete,
+}
+
+/// Something that happened to a check.
+#[derive(Clone, Debug, Serialize)]
+pub struct Event {
+    pub timestamp: DateTime<Utc>,
+    pub id: String,
+    pub event: EventKind,
+
+    /// Correlation ID of the HTTP request that caused the event, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// Bounded in-memory log of the most recent events, for reviewing what
+/// happened before and during an incident. Once it is full, the oldest
+/// events are discarded.
+pub struct EventLog {
+    capacity: usize,
+    events: Mutex<VecDeque<Event>>,
+}
+
+impl EventLog {
+    /// Creates a log that keeps at most `capacity` events; with a capacity
+    /// of zero, nothing is kept.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            events: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    pub fn record(&self, event: Event) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut events = self.events.lock().unwrap();
+        if events.len() == self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Returns the `limit` most recent events, oldest first, optionally only
+    /// those of check `id`.
+    pub fn recent(&self, id: Option<&str>, limit: usize) -> Vec<Event> {
+        let events = self.events.lock().unwrap();
+        let mut recent: Vec<Event> = events.iter()
+            .rev()
+            .filter(|e| id.is_none_or(|id| e.id == id))
+            .take(limit)
+            .cloned()
+            .collect();
+
+        recent.reverse();
+        recent
+    }
+}
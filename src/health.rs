@@ -0,0 +1,156 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use log::error;
+use tokio::task::JoinHandle;
+
+/// Long-running tasks that pings and alerts depend on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Worker {
+    PingReceiver,
+    Alerter,
+}
+
+impl Worker {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Worker::PingReceiver => "ping receiver",
+            Worker::Alerter => "alerter",
+        }
+    }
+}
+
+/// Readiness and liveness of the workers, shared with the HTTP API so that
+/// it only reports success (and accepts pings) once startup has completed,
+/// and stops doing so once a worker died.
+#[derive(Debug)]
+pub struct Health {
+    ready: AtomicBool,
+    ping_receiver: AtomicBool,
+    alerter: AtomicBool,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self {
+            ready: AtomicBool::new(false),
+            ping_receiver: AtomicBool::new(true),
+            alerter: AtomicBool::new(true),
+        }
+    }
+}
+
+impl Health {
+    fn flag(&self, worker: Worker) -> &AtomicBool {
+        match worker {
+            Worker::PingReceiver => &self.ping_receiver,
+            Worker::Alerter => &self.alerter,
+        }
+    }
+
+    /// Marks startup as completed: the notifiers are built (and verified, if
+    /// enabled) and the workers are running.
+    pub fn mark_ready(&self) {
+        self.ready.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Relaxed)
+    }
+
+    pub fn mark_dead(&self, worker: Worker) {
+        self.flag(worker).store(false, Ordering::Relaxed);
+    }
+
+    pub fn is_alive(&self, worker: Worker) -> bool {
+        self.flag(worker).load(Ordering::Relaxed)
+    }
+
+    /// Returns a worker that died, if any.
+    pub fn dead_worker(&self) -> Option<Worker> {
+        [Worker::PingReceiver, Worker::Alerter].into_iter().find(|w| !self.is_alive(*w))
+    }
+}
+
+/// Waits for the task of `worker` and, if it panicked or was cancelled, logs
+/// why and marks the worker as dead. The worker is not restarted, as its channel
+/// receiver was dropped along with it; the process has to be restarted,
+/// which a failing readiness probe lets the orchestrator do.
+pub fn supervise(worker: Worker, health: Arc<Health>, task: JoinHandle<()>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        if let Err(e) = task.await {
+            health.mark_dead(worker);
+
+            if e.is_panic() {
+                error!("{} panicked; pings and alerts are no longer processed reliably: {}", worker.as_str(), e);
+            } else {
+                error!("{} was cancelled; pings and alerts are no longer processed reliably", worker.as_str());
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc;
+    use warp::http::StatusCode;
+
+    use super::*;
+    use crate::config::tests::settings;
+    use crate::filters;
+    use crate::metrics::Metrics;
+    use crate::state;
+
+    /// Status of `GET /healthz` and of a ping for check `a`.
+    async fn statuses(health: &Arc<Health>) -> (StatusCode, StatusCode) {
+        let healthz = warp::test::request()
+            .path("/healthz")
+            .reply(&filters::probe_routes(health.clone()))
+            .await;
+
+        let (tx_ping, _rx_ping) = mpsc::channel(1);
+        let policy = filters::ping_policy(&settings(""), state::new_shared_state(), health.clone());
+        let ping = warp::test::request()
+            .method("POST")
+            .path("/ping/a")
+            .reply(&filters::ping(tx_ping, Arc::new(Metrics::default()), None, false, policy))
+            .await;
+
+        (healthz.status(), ping.status())
+    }
+
+    async fn supervised(task: JoinHandle<()>) -> Arc<Health> {
+        let health = Arc::new(Health::default());
+        health.mark_ready();
+
+        supervise(Worker::Alerter, health.clone(), task).await.unwrap();
+        health
+    }
+
+    #[tokio::test]
+    async fn aborted_worker_fails_readiness_and_rejects_pings() {
+        let task = tokio::spawn(std::future::pending::<()>());
+        task.abort();
+
+        let health = supervised(task).await;
+
+        assert_eq!(health.dead_worker(), Some(Worker::Alerter));
+        assert_eq!(statuses(&health).await, (StatusCode::SERVICE_UNAVAILABLE, StatusCode::SERVICE_UNAVAILABLE));
+    }
+
+    #[tokio::test]
+    async fn panicked_worker_fails_readiness_and_rejects_pings() {
+        let health = supervised(tokio::spawn(async { panic!("worker failed") })).await;
+
+        assert_eq!(health.dead_worker(), Some(Worker::Alerter));
+        assert_eq!(statuses(&health).await, (StatusCode::SERVICE_UNAVAILABLE, StatusCode::SERVICE_UNAVAILABLE));
+    }
+
+    #[tokio::test]
+    async fn finished_worker_stays_alive() {
+        let health = supervised(tokio::spawn(async {})).await;
+
+        assert_eq!(health.dead_worker(), None);
+        assert_eq!(statuses(&health).await.0, StatusCode::OK);
+    }
+}
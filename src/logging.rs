@@ -0,0 +1,50 @@
+use std::io::Write;
+
+use log::kv::{self, Key, Value, Visitor};
+use serde_json::{json, Map};
+
+use crate::config::{LogFormat, Settings};
+
+/// Initializes the global logger. The log level is taken from `level` (using
+/// the syntax of `RUST_LOG`) if given, then from `RUST_LOG`, and finally from
+/// the settings.
+pub fn init(settings: &Settings, level: Option<&str>) {
+    let mut builder = env_logger::Builder::new();
+
+    if let Some(default) = settings.log_level() {
+        builder.filter_level(default.filter());
+    }
+
+    // Directives parsed later replace earlier ones for the same module.
+    builder.parse_env(env_logger::Env::default());
+
+    if let Some(level) = level {
+        builder.parse_filters(level);
+    }
+
+    if settings.log.format == LogFormat::Json {
+        builder.format(|buf, record| {
+            let mut fields = Map::new();
+            fields.insert("timestamp".to_string(), json!(buf.timestamp_micros().to_string()));
+            fields.insert("level".to_string(), json!(record.level().as_str()));
+            fields.insert("target".to_string(), json!(record.target()));
+            fields.insert("message".to_string(), json!(record.args().to_string()));
+
+            // Key-value pairs attached to the record, e.g. the check ID.
+            let _ = record.key_values().visit(&mut FieldCollector(&mut fields));
+
+            writeln!(buf, "{}", serde_json::Value::Object(fields))
+        });
+    }
+
+    builder.init();
+}
+
+struct FieldCollector<'a>(&'a mut Map<String, serde_json::Value>);
+
+impl<'kvs> Visitor<'kvs> for FieldCollector<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        self.0.insert(key.to_string(), json!(value.to_string()));
+        Ok(())
+    }
+}
@@ -1,67 +1,123 @@
 extern crate chrono;
-extern crate timer;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::net::SocketAddr;
-use std::sync::mpsc::{Receiver, SyncSender, Sender};
-use std::sync::{mpsc};
-use std::thread;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 use clap::Parser;
+use futures::future::{BoxFuture, FutureExt, Shared};
 
-use log::{debug, info, warn};
-use timer::Guard;
+use log::{debug, error, info, warn};
+use tokio::sync::{mpsc, watch};
+use tokio::task::{JoinHandle, JoinSet};
+use tokio::time::Instant;
 use warp::Filter;
 
-use crate::config::{NotifierSettings, Settings};
-use crate::notifier::{NoOpNotifier, Notifier, Alert};
+use crate::clock::{Clock, SystemClock};
+use crate::config::{CorsSettings, MaintenanceWindow, NotifierSettings, RouteSet, Settings, Severity};
+use crate::error::DodemansknopError;
+use crate::events::EventLog;
+use crate::health::{Health, Worker};
+use crate::metrics::Metrics;
+use crate::retryqueue::RetryQueue;
+use crate::scheduler::{AlerterMessage, Command, Ping};
+use crate::state::{CheckStatus, SharedState};
+use crate::store::StateStore;
+use crate::notifier::{CircuitBreakerNotifier, CompositeNotifier, NoOpNotifier, Notifier, TimeoutNotifier, Alert, AlertEvent, Heartbeat, SharedNotifier};
 use crate::notifiers::webhook::WebhookNotifier;
 use crate::notifiers::slack::SlackNotifier;
+use crate::notifiers::pagerduty::PagerDutyNotifier;
+use crate::notifiers::discord::DiscordNotifier;
+use crate::notifiers::opsgenie::OpsgenieNotifier;
+use crate::notifiers::teams::TeamsNotifier;
+use crate::notifiers::telegram::TelegramNotifier;
+use crate::notifiers::file::FileNotifier;
+use crate::notifiers::mqtt::MqttNotifier;
+use crate::notifiers::stdout::StdoutNotifier;
+use crate::notifiers::twilio::TwilioNotifier;
+use crate::notifiers::matrix::MatrixNotifier;
+use crate::notifiers::gotify::GotifyNotifier;
+use crate::notifiers::ntfy::NtfyNotifier;
+use crate::notifiers::syslog::SyslogNotifier;
 
 mod notifier;
 
-mod notifiers { pub mod webhook; pub mod slack; }
+mod notifiers { pub mod webhook; pub mod slack; pub mod pagerduty; pub mod discord; pub mod opsgenie; pub mod teams; pub mod telegram; pub mod file; pub mod mqtt; pub mod stdout; pub mod twilio; pub mod matrix; pub mod gotify; pub mod ntfy; pub mod syslog; }
 
 mod config;
 
+mod metrics;
+
+mod state;
+
+mod scheduler;
+mod logging;
+mod ratelimit;
+mod retryqueue;
+mod error;
+mod clock;
+mod store;
+mod events;
+mod health;
+
 #[derive(Parser, Default, Debug)]
 #[command(author = "Martin Helmich <m.helmich@mittwald.de>", version, about="A simple dead mans switch")]
 struct Arguments {
     #[arg(short, long="config")]
-    /// Path to the configuration file
+    /// Path to the configuration file, or to a directory of configuration fragments
     config_file: Option<String>,
 
-    #[arg(short, long)]
-    /// Address to bind to
+    #[arg(short, long, visible_alias = "bind")]
+    /// Address to bind to; overrides the "server" section of the configuration file
     listen_addr: Option<String>,
+
+    #[arg(long)]
+    /// Log level (e.g. "debug" or "info,dodemansknop=debug"); overrides RUST_LOG
+    log_level: Option<String>,
 }
 
-fn build_notifier_set(cfx: &Settings) -> Result<Vec<Box<dyn Notifier>>, String> {
-    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+fn build_notifier_set(cfx: &Settings, clock: &Arc<dyn Clock>) -> Result<CompositeNotifier, DodemansknopError> {
+    let mut notifiers: Vec<(String, Box<dyn Notifier>)> = Vec::new();
 
     for notifier_setting in cfx.notifiers.iter() {
-        match build_notifier(notifier_setting) {
-            Ok(notifier) => notifiers.push(notifier),
+        match build_notifier(notifier_setting, clock) {
+            Ok(notifier) => {
+                let name = notifier_setting.name().to_string();
+
+                // The timeout is applied first, so that calls that are cut
+                // off count towards opening the circuit.
+                let timeout = Duration::from_secs(cfx.notifier_send_timeout_seconds);
+                let notifier: Box<dyn Notifier> = Box::new(TimeoutNotifier::new(name.clone(), notifier, timeout));
+                let notifier: Box<dyn Notifier> = match notifier_setting.circuit_breaker {
+                    Some(ref cb) => Box::new(CircuitBreakerNotifier::new(
+                        name.clone(),
+                        notifier,
+                        cb.failure_threshold,
+                        Duration::from_secs(cb.cooldown_seconds),
+                    )),
+                    None => notifier,
+                };
+                notifiers.push((name, notifier))
+            },
             Err(e) => {
-                return Err(format!("failed to build notifier: {}", e))
+                return Err(DodemansknopError::Notifiers(vec![(notifier_setting.name().to_string(), e)]))
             },
         }
     }
 
-    Ok(notifiers)
+    Ok(CompositeNotifier::new(notifiers))
+}
+
+/// Formats validation problems as an indented list, one per line.
+fn format_problems(problems: &[String]) -> String {
+    problems.iter().map(|p| format!("\n  - {}", p)).collect()
 }
 
-fn build_notifier(cfg: &NotifierSettings) -> Result<Box<dyn Notifier>, String> {
+fn build_notifier(cfg: &NotifierSettings, clock: &Arc<dyn Clock>) -> Result<Box<dyn Notifier>, DodemansknopError> {
     match cfg.notifier_type.as_str() {
         "webhook" => match cfg.webhook {
-            Some(ref wh) => Ok(
-                Box::new(WebhookNotifier::new(
-                    wh.url.clone(),
-                    wh.method.clone(),
-                    wh.body.clone(),
-                    wh.headers.clone().unwrap_or(vec![])
-                )),
-            ),
-            None => Err("no webhook settings found".to_string()),
+            Some(ref wh) => Ok(Box::new(WebhookNotifier::new(wh)?)),
+            None => Err(DodemansknopError::Config("no webhook settings found".to_string())),
         },
         "slack" => match cfg.slack {
             Some(ref wh) => Ok(
@@ -69,155 +125,2245 @@ fn build_notifier(cfg: &NotifierSettings) -> Result<Box<dyn Notifier>, String> {
                     wh.url.clone(),
                     wh.icon_emoji.clone(),
                     wh.color.clone(),
+                    wh.channel.clone(),
+                    wh.username.clone(),
+                )),
+            ),
+            None => Err(DodemansknopError::Config("no slack settings found".to_string())),
+        },
+        "pagerduty" => match cfg.pagerduty {
+            Some(ref pd) => Ok(
+                Box::new(PagerDutyNotifier::new(
+                    pd.routing_key.clone(),
+                    pd.severity.clone(),
+                    pd.source.clone(),
                 )),
             ),
-            None => Err("no slack settings found".to_string()),
+            None => Err(DodemansknopError::Config("no pagerduty settings found".to_string())),
+        },
+        "discord" => match cfg.discord {
+            Some(ref d) => Ok(
+                Box::new(DiscordNotifier::new(
+                    d.url.clone(),
+                    d.username.clone(),
+                    d.avatar_url.clone(),
+                )),
+            ),
+            None => Err(DodemansknopError::Config("no discord settings found".to_string())),
+        },
+        "opsgenie" => match cfg.opsgenie {
+            Some(ref og) => Ok(
+                Box::new(OpsgenieNotifier::new(
+                    og.api_key.clone(),
+                    og.region.clone(),
+                )?),
+            ),
+            None => Err(DodemansknopError::Config("no opsgenie settings found".to_string())),
+        },
+        "teams" => match cfg.teams {
+            Some(ref t) => Ok(
+                Box::new(TeamsNotifier::new(
+                    t.url.clone(),
+                    t.theme_color.clone(),
+                )),
+            ),
+            None => Err(DodemansknopError::Config("no teams settings found".to_string())),
+        },
+        "telegram" => match cfg.telegram {
+            Some(ref t) => Ok(
+                Box::new(TelegramNotifier::new(
+                    t.bot_token.clone(),
+                    t.chat_id.clone(),
+                    t.parse_mode.clone(),
+                )?),
+            ),
+            None => Err(DodemansknopError::Config("no telegram settings found".to_string())),
+        },
+        "file" => match cfg.file {
+            Some(ref f) => Ok(
+                Box::new(FileNotifier::new(
+                    f.path.clone(),
+                    f.append,
+                    clock.clone(),
+                )?),
+            ),
+            None => Err(DodemansknopError::Config("no file settings found".to_string())),
+        },
+        "mqtt" => match cfg.mqtt {
+            Some(ref m) => Ok(Box::new(MqttNotifier::new(m)?)),
+            None => Err(DodemansknopError::Config("no mqtt settings found".to_string())),
+        },
+        "twilio" => match cfg.twilio {
+            Some(ref t) => Ok(Box::new(TwilioNotifier::new(t)?)),
+            None => Err(DodemansknopError::Config("no twilio settings found".to_string())),
+        },
+        "matrix" => match cfg.matrix {
+            Some(ref m) => Ok(Box::new(MatrixNotifier::new(m)?)),
+            None => Err(DodemansknopError::Config("no matrix settings found".to_string())),
+        },
+        "gotify" => match cfg.gotify {
+            Some(ref g) => Ok(Box::new(GotifyNotifier::new(g)?)),
+            None => Err(DodemansknopError::Config("no gotify settings found".to_string())),
         },
+        "ntfy" => match cfg.ntfy {
+            Some(ref n) => Ok(Box::new(NtfyNotifier::new(n)?)),
+            None => Err(DodemansknopError::Config("no ntfy settings found".to_string())),
+        },
+        "syslog" => match cfg.syslog {
+            Some(ref sl) => Ok(Box::new(SyslogNotifier::new(sl)?)),
+            None => Err(DodemansknopError::Config("no syslog settings found".to_string())),
+        },
+        "stdout" => Ok(Box::new(StdoutNotifier::new(clock.clone()))),
         "noop" => Ok(Box::new(NoOpNotifier {})),
-        t => Err(format!("unsupported notifier: {}", t))
+        t => Err(DodemansknopError::Config(format!("unsupported notifier: {}", t)))
     }
 }
 
 fn main() {
-    env_logger::init();
-
     let args = Arguments::parse();
-    let settings = config::retrieve_settings(args.config_file).unwrap();
+    // The logger is configured by the settings, so errors while loading
+    // them can only be printed directly.
+    let settings = match config::retrieve_settings(args.config_file.clone()) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("could not load configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    logging::init(&settings, args.log_level.as_deref());
+
+    if let Err(problems) = settings.validate() {
+        error!("invalid configuration:{}", format_problems(&problems));
+        std::process::exit(1);
+    }
+
+    for deprecation in settings.deprecations() {
+        warn!("{}", deprecation);
+    }
 
     info!("loaded settings: {:?}", settings);
 
-    let (tx_ping, rx_ping): (SyncSender<String>, Receiver<String>) = mpsc::sync_channel(32);
-    let (tx_alert, rx_alert): (Sender<Alert>, Receiver<Alert>) = mpsc::channel();
-    let notifier_set = build_notifier_set(&settings).unwrap();
+    let listen_addrs = match args.listen_addr {
+        Some(_) if !settings.server.listeners.is_empty() => Err("--listen-addr cannot be combined with server.listeners".to_string()),
+        Some(addr) => addr.parse::<SocketAddr>()
+            .map(|addr| vec![(addr, RouteSet::All)])
+            .map_err(|e| format!("invalid listen address '{}': {}", addr, e)),
+        None => settings.server.listen_addrs(),
+    };
+
+    let listen_addrs = match listen_addrs {
+        Ok(addrs) => addrs,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let (tx_ping, rx_ping) = mpsc::channel::<Ping>(settings.ping_queue_capacity());
+    let (tx_alert, rx_alert) = mpsc::unbounded_channel::<AlerterMessage>();
+    let (tx_commands, rx_commands) = mpsc::unbounded_channel::<Command>();
+    let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+    let notifier = match build_notifier_set(&settings, &clock) {
+        Ok(n) => n,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let notifier: SharedNotifier = Arc::new(RwLock::new(Arc::new(notifier)));
+    let metrics = Arc::new(Metrics::default());
+    let check_state = state::new_shared_state();
 
-    run_alerter_thread(rx_alert, notifier_set);
-    run_ping_receiver_thread(rx_ping, tx_alert, settings.clone());
+    let store: Option<Arc<dyn StateStore>> = match settings.state_backend() {
+        Some(backend) => match store::open(&backend) {
+            Ok(store) => {
+                let restored = store.load();
+                info!("restored {} checks from {:?}", restored.len(), backend);
+                *check_state.lock().unwrap() = restored;
+                Some(Arc::from(store))
+            },
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
 
     tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()
         .unwrap()
         .block_on(async move {
-            serve_api(args.listen_addr.unwrap_or(String::from("0.0.0.0:3030")), tx_ping).await;
+            // The API is served right away, so that health checks can tell
+            // that the server is starting; pings are rejected until startup
+            // has completed.
+            let health = Arc::new(Health::default());
+            let server = serve_api(listen_addrs, tx_ping, tx_commands.clone(), health.clone(), metrics.clone(), check_state.clone(), &settings);
+
+            let startup = async {
+                if settings.verify_notifier_on_startup {
+                    let n = notifier.read().unwrap().clone();
+                    match n.verify().await {
+                        Ok(_) => info!("all notifiers are reachable"),
+                        Err(e) if settings.fail_on_verify => {
+                            error!("notifier verification failed: {}", e);
+                            std::process::exit(1);
+                        },
+                        Err(e) => warn!("notifier verification failed; alerts may not be delivered: {}", e),
+                    }
+                }
+
+                let retry_queue = settings.retry_queue.clone().map(|q| {
+                    let queue = RetryQueue::load(q, clock.clone());
+                    info!("loaded {} notifications from the retry queue", queue.len());
+                    queue
+                });
+
+                let (tx_settings, rx_settings) = watch::channel(Arc::new(settings.clone()));
+                let alerter = run_alerter_task(rx_alert, notifier.clone(), metrics.clone(), check_state.clone(), rx_settings, retry_queue, clock.clone());
+                let alerter = health::supervise(Worker::Alerter, health.clone(), alerter);
+                if let Some(ref heartbeat) = settings.heartbeat {
+                    run_heartbeat_task(notifier.clone(), check_state.clone(), Duration::from_secs(heartbeat.interval_seconds), heartbeat.notifiers.clone(), clock.clone());
+                }
+
+                let timeout_ctx = scheduler::TimeoutContext::new(tx_alert, check_state.clone(), metrics.clone(), clock.clone(), store, Arc::new(EventLog::new(settings.event_log_size)));
+                let ping_receiver = scheduler::run_ping_receiver_task(rx_ping, rx_commands, timeout_ctx, settings.clone(), notifier);
+                let ping_receiver = health::supervise(Worker::PingReceiver, health.clone(), ping_receiver);
+
+                run_reload_task(args.config_file, tx_commands, tx_settings, clock);
+
+                health.mark_ready();
+                info!("startup completed; accepting pings");
+
+                (ping_receiver, alerter)
+            };
+
+            let (_, (ping_receiver, alerter)) = tokio::join!(server, startup);
+
+            // At this point, the HTTP server (and with it, all ping senders)
+            // has been dropped, which lets the ping receiver terminate. This
+            // in turn drops the alert sender, so that the alerter can drain
+            // all remaining alerts and terminate.
+            info!("http server stopped; waiting for ping receiver to stop");
+            if let Err(e) = ping_receiver.await {
+                warn!("ping receiver failed: {}", e);
+            }
+
+            let shutdown_timeout = Duration::from_secs(settings.shutdown_timeout_seconds());
+
+            info!("ping receiver stopped; waiting up to {:?} for pending alerts to be sent", shutdown_timeout);
+            match tokio::time::timeout(shutdown_timeout, alerter).await {
+                Ok(_) => info!("all pending alerts sent; shutting down"),
+                Err(_) => warn!("pending alerts were not sent within {:?}; shutting down anyway", shutdown_timeout),
+            }
         });
 }
 
-fn run_alerter_thread(rx_alert: Receiver<Alert>, notifier_set: Vec<Box<dyn Notifier>>) {
-    thread::spawn(move || {
-        loop {
-            let r = rx_alert.recv();
-            if r.is_err() {
-                warn!("error while receiving alert: {}", r.err().unwrap());
-                continue;
-            }
+/// Periodically sends a summary of all checks to the `targets`, starting one
+/// `interval` after startup.
+fn run_heartbeat_task(notifier: SharedNotifier, check_state: SharedState, interval: Duration, targets: Option<Vec<String>>, clock: Arc<dyn Clock>) {
+    tokio::spawn(async move {
+        let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
 
-            let alert = r.unwrap();
+        loop {
+            ticks.tick().await;
 
-            for notifier in notifier_set.iter() {
-                match notifier.notify_failure(alert.clone()) {
-                    Ok(_) => info!("failure notified"),
-                    Err(e) => warn!("error while notifying about failure: {}", e)
+            let mut heartbeat = Heartbeat { targets: targets.clone(), healthy: 0, overdue: 0, paused: 0, timestamp: clock.now() };
+            for state in check_state.lock().unwrap().values() {
+                match state.status {
+                    CheckStatus::Healthy | CheckStatus::Registered => heartbeat.healthy += 1,
+                    CheckStatus::Alerted => heartbeat.overdue += 1,
+                    CheckStatus::Paused => heartbeat.paused += 1,
                 }
             }
+
+            // Picked up on every tick, so that reloaded notifiers are used.
+            let n = notifier.read().unwrap().clone();
+            match n.notify_heartbeat(&heartbeat).await {
+                Ok(_) => info!("heartbeat sent: {}", heartbeat.message()),
+                Err(e) => error!("could not send heartbeat; alerts may not be delivered either: {}", e),
+            }
         }
     });
 }
 
-fn run_ping_receiver_thread(rx_ping: Receiver<String>, tx_alert: Sender<Alert>, settings: Settings) {
-    thread::spawn(move || {
-        let timer = timer::Timer::new();
-        let delay = chrono::Duration::seconds(settings.timeout.unwrap_or(30));
+/// How often the retry queue is checked for notifications that are due.
+const RETRY_QUEUE_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Sends the alerts raised by the ping receiver. The cooldown, batching and
+/// maintenance windows are taken from the latest settings in `settings`,
+/// which the reload task replaces; the notifier concurrency is fixed at
+/// startup.
+fn run_alerter_task(mut rx_alert: mpsc::UnboundedReceiver<AlerterMessage>, notifier: SharedNotifier, metrics: Arc<Metrics>, check_state: SharedState, mut settings: watch::Receiver<Arc<Settings>>, mut retry_queue: Option<RetryQueue>, clock: Arc<dyn Clock>) -> JoinHandle<()> {
+    let (mut cooldown, mut batch_window, mut maintenance_windows) = alerter_settings(&settings.borrow_and_update());
+
+    let mut dispatcher = Dispatcher::new(notifier, metrics, settings.borrow().notifier_concurrency);
 
-        let mut active_timers: HashMap<String, Guard> = HashMap::new();
+    tokio::spawn(async move {
+
+        // Time of the last failure notification, keyed by check ID and
+        // targets, so that escalations are not suppressed by the preceding
+        // alert.
+        let mut last_failures: HashMap<(String, Option<Vec<String>>), Instant> = HashMap::new();
+
+        // Checks whose failures were notified, or only suppressed (by a
+        // maintenance window or an acknowledgement), since their last
+        // recovery. A recovery is only notified if the failure was, too.
+        let mut notified: HashSet<String> = HashSet::new();
+        let mut silenced: HashSet<String> = HashSet::new();
+
+        // Groups all of whose checks are failing, and for which a single
+        // group alert was sent instead.
+        let mut groups_down: HashSet<String> = HashSet::new();
+
+        // Failures held back because a dependency of their check is failing.
+        // They are sent once all dependencies recovered, unless the check
+        // recovered first.
+        let mut held: HashMap<String, Alert> = HashMap::new();
+
+        let mut retry_tick = tokio::time::interval(RETRY_QUEUE_POLL_INTERVAL);
+
+        // Failures collected while batching, and when they are sent.
+        let mut batch: Vec<Alert> = Vec::new();
+        let mut batch_deadline: Option<tokio::time::Instant> = None;
 
         loop {
-            let r = rx_ping.recv();
-            if r.is_err() {
-                warn!("error while receiving ping: {}", r.err().unwrap());
+            tokio::select! {
+                // recv() only returns None after all senders have been
+                // dropped and the channel has been drained, which happens on
+                // shutdown.
+                message = rx_alert.recv() => {
+                    // Recoveries of deleted checks carry their group, which
+                    // cannot be looked up anymore.
+                    let (alert, deleted_group) = match message {
+                        Some(AlerterMessage::Alert(alert)) => (alert, None),
+                        Some(AlerterMessage::Deleted { recovery, group }) => (recovery, Some(group)),
+                        Some(AlerterMessage::Reset) => {
+                            info!("checks were reset; forgetting {} notified failures and {} queued retries", notified.len(), retry_queue.as_ref().map_or(0, |q| q.len()));
+
+                            last_failures.clear();
+                            notified.clear();
+                            silenced.clear();
+                            groups_down.clear();
+                            held.clear();
+                            batch.clear();
+                            batch_deadline = None;
+
+                            if let Some(ref mut queue) = retry_queue {
+                                queue.clear();
+                            }
+
+                            continue;
+                        },
+                        None => break,
+                    };
+
+                    match alert.event {
+                        AlertEvent::Failure => {
+                            if check_state.lock().unwrap().get(&alert.id).is_some_and(|s| s.acknowledged) {
+                                info!(id = alert.id, event = alert.event.as_str(); "check {} is acknowledged; suppressing failure alert", alert.id);
+                                silenced.insert(alert.id);
+                                continue;
+                            }
+
+                            if maintenance_windows.iter().any(|w| w.is_active(clock.now())) {
+                                info!(id = alert.id, event = alert.event.as_str(); "maintenance window is active; suppressing failure alert for {}", alert.id);
+                                silenced.insert(alert.id);
+                                continue;
+                            }
+
+                            if let Some(dependency) = failing_dependency(&check_state, &alert.id) {
+                                info!(id = alert.id, event = alert.event.as_str(); "dependency {} of {} is failing; holding back failure alert", dependency, alert.id);
+                                silenced.insert(alert.id.clone());
+                                held.insert(alert.id.clone(), alert);
+                                continue;
+                            }
+
+                            let key = (alert.id.clone(), alert.targets.clone());
+                            if let (Some(cooldown), Some(last)) = (cooldown, last_failures.get(&key)) {
+                                if last.elapsed() < cooldown {
+                                    debug!(id = alert.id, event = alert.event.as_str(); "failure for {} was already notified {:?} ago; suppressing alert", alert.id, last.elapsed());
+                                    continue;
+                                }
+                            }
+
+                            if cooldown.is_some() {
+                                last_failures.insert(key, Instant::now());
+                            }
+
+                            if let Some((group, members)) = group_members(&check_state, &alert.id) {
+                                if members.iter().all(|(_, status)| *status == CheckStatus::Alerted) {
+                                    silenced.insert(alert.id.clone());
+
+                                    if !groups_down.insert(group.clone()) {
+                                        info!(id = alert.id, event = alert.event.as_str(); "group {} is failing; suppressing failure alert for {}", group, alert.id);
+                                        continue;
+                                    }
+
+                                    // Batched failures of the group are covered by the
+                                    // group alert, too.
+                                    batch.retain(|a| {
+                                        let covered = members.iter().any(|(id, _)| *id == a.id);
+                                        if covered {
+                                            silenced.insert(a.id.clone());
+                                        }
+                                        !covered
+                                    });
+
+                                    info!(id = group, event = alert.event.as_str(); "all checks of group {} are failing; sending a single group alert", group);
+                                    notified.insert(group.clone());
+                                    dispatcher.dispatch(None, group_alert(&group, &members, alert));
+                                    continue;
+                                }
+                            }
+
+                            if let Some(window) = batch_window {
+                                batch_deadline.get_or_insert_with(|| Instant::now() + window);
+                                batch.push(alert);
+                                continue;
+                            }
+
+                            notified.insert(alert.id.clone());
+                        },
+                        AlertEvent::Recovery => {
+                            last_failures.retain(|(id, _), _| *id != alert.id);
+
+                            held.remove(&alert.id);
+                            for failure in release_held(&mut held, &check_state) {
+                                info!(id = failure.id, event = failure.event.as_str(); "dependencies of {} recovered, but it is still failing; sending held back failure alert", failure.id);
+                                silenced.remove(&failure.id);
+                                notified.insert(failure.id.clone());
+                                dispatcher.dispatch(None, failure);
+                            }
+
+                            if let Some(ref mut queue) = retry_queue {
+                                queue.discard_failures(&alert.id);
+                            }
+
+                            // The group alert is resolved once all checks of the
+                            // group recovered.
+                            let group = match deleted_group {
+                                Some(group) => group.map(|g| {
+                                    let members = members_of(&check_state, &g);
+                                    (g, members)
+                                }),
+                                None => group_members(&check_state, &alert.id),
+                            };
+
+                            if let Some((group, members)) = group {
+                                if groups_down.contains(&group) && members.iter().all(|(_, status)| *status != CheckStatus::Alerted) {
+                                    groups_down.remove(&group);
+
+                                    if let Some(ref mut queue) = retry_queue {
+                                        queue.discard_failures(&group);
+                                    }
+
+                                    if notified.remove(&group) {
+                                        info!(id = group, event = alert.event.as_str(); "all checks of group {} recovered; sending group recovery", group);
+                                        dispatcher.dispatch(None, group_alert(&group, &members, alert.clone()));
+                                    }
+                                }
+                            }
+
+                            let batched = batch.len();
+                            batch.retain(|a| a.id != alert.id);
+
+                            let was_notified = notified.remove(&alert.id);
+                            if batch.len() != batched && !was_notified {
+                                info!(id = alert.id, event = alert.event.as_str(); "{} recovered before its batched failure was sent; dropping both alerts", alert.id);
+                                continue;
+                            }
+
+                            if silenced.remove(&alert.id) && !was_notified {
+                                info!(id = alert.id, event = alert.event.as_str(); "failure of {} was suppressed; suppressing recovery alert", alert.id);
+                                continue;
+                            }
+                        },
+                    }
+
+                    dispatcher.dispatch(None, alert);
+                },
+                // changed() fails once the reload task is gone, which
+                // disables this branch.
+                Ok(()) = settings.changed() => {
+                    (cooldown, batch_window, maintenance_windows) = alerter_settings(&settings.borrow_and_update());
+                    debug!("alerter settings reloaded");
+                },
+                _ = sleep_until_deadline(batch_deadline), if batch_deadline.is_some() => {
+                    batch_deadline = None;
+                    flush_batch(&mut batch, &mut notified, &mut dispatcher);
+                },
+                _ = retry_tick.tick(), if retry_queue.is_some() => {
+                    let due = retry_queue.as_mut().map(|q| q.take_due()).unwrap_or_default();
+                    for (key, alert) in due {
+                        debug!(id = alert.id, event = alert.event.as_str(); "retrying {} of {}", alert.event.as_str(), alert.id);
+
+                        dispatcher.dispatch(Some(key), alert);
+                    }
+                },
+                Some(result) = dispatcher.join_next() => {
+                    if let Ok((key, retry)) = result {
+                        record_dispatch(&mut retry_queue, key, retry);
+                    }
+                },
+            }
+        }
+
+        flush_batch(&mut batch, &mut notified, &mut dispatcher);
+
+        info!("alert channel closed; waiting for {} pending notifications", dispatcher.len());
+        while let Some(result) = dispatcher.join_next().await {
+            if let Ok((key, retry)) = result {
+                record_dispatch(&mut retry_queue, key, retry);
+            }
+        }
+
+        if let Some(ref queue) = retry_queue {
+            if !queue.is_empty() {
+                info!("{} notifications remain in the retry queue", queue.len());
+            }
+        }
+
+        info!("alerter stopped");
+    })
+}
+
+/// The alert cooldown, batch window and maintenance windows of `settings`.
+/// The windows' schedules are parsed once here, rather than for every alert.
+fn alerter_settings(settings: &Settings) -> (Option<Duration>, Option<Duration>, Vec<MaintenanceWindow>) {
+    let maintenance_windows = settings.maintenance_windows.iter()
+        .map(|w| w.window().expect("maintenance windows are validated with the settings"))
+        .collect();
+
+    (
+        settings.alert_cooldown_seconds.map(Duration::from_secs),
+        settings.alert_batch_seconds.map(Duration::from_secs),
+        maintenance_windows,
+    )
+}
+
+/// Returns a failing check that check `id` depends on, directly or through
+/// other dependencies, if there is one.
+fn failing_dependency(check_state: &SharedState, id: &str) -> Option<String> {
+    let checks = check_state.lock().unwrap();
+
+    let mut pending: Vec<&str> = vec![id];
+    let mut visited: HashSet<&str> = HashSet::new();
+
+    while let Some(current) = pending.pop() {
+        for dep in checks.get(current).map(|s| s.depends_on.as_slice()).unwrap_or_default() {
+            // Cycles are rejected by validation; this also keeps shared
+            // dependencies from being looked at twice.
+            if !visited.insert(dep) {
                 continue;
             }
 
-            let id = r.unwrap();
-            let idc = id.clone();
+            if checks.get(dep).is_some_and(|s| s.status == CheckStatus::Alerted) {
+                return Some(dep.clone());
+            }
+            pending.push(dep);
+        }
+    }
 
-            let tx_cpy = tx_alert.clone();
+    None
+}
 
-            debug!("received ping for {}; timeout is {}", id, delay);
+/// Removes and returns the held back failures of checks that are still
+/// failing, but whose dependencies all recovered.
+fn release_held(held: &mut HashMap<String, Alert>, check_state: &SharedState) -> Vec<Alert> {
+    let mut released = Vec::new();
 
-            active_timers.insert(id, timer.schedule_with_delay(delay, move || {
-                info!("missed ping for {}; scheduling alert", idc);
+    let ids: Vec<String> = held.keys().cloned().collect();
+    for id in ids {
+        if failing_dependency(check_state, &id).is_some() {
+            continue;
+        }
 
-                let alert = Alert{
-                    id: idc.clone(),
-                };
+        let still_failing = check_state.lock().unwrap().get(&id).is_some_and(|s| s.status == CheckStatus::Alerted);
+        if let Some(alert) = held.remove(&id) {
+            if still_failing {
+                released.push(alert);
+            }
+        }
+    }
 
-                match tx_cpy.send(alert) {
-                    Ok(_) => debug!("alert scheduled for {}", idc),
-                    Err(e) => warn!("error while scheduling alert: {}", e)
-                }
-            }));
+    released
+}
+
+/// Returns the group of check `id` along with the status of each of its
+/// checks, or `None` if the check does not belong to a group.
+fn group_members(check_state: &SharedState, id: &str) -> Option<(String, Vec<(String, CheckStatus)>)> {
+    let group = check_state.lock().unwrap().get(id)?.group.clone()?;
+    let members = members_of(check_state, &group);
+
+    Some((group, members))
+}
+
+/// Returns the checks of `group` along with their status, sorted by ID.
+fn members_of(check_state: &SharedState, group: &str) -> Vec<(String, CheckStatus)> {
+    let checks = check_state.lock().unwrap();
+
+    let mut members: Vec<(String, CheckStatus)> = checks.iter()
+        .filter(|(_, state)| state.group.as_deref() == Some(group))
+        .map(|(id, state)| (id.clone(), state.status))
+        .collect();
+    members.sort_by(|a, b| a.0.cmp(&b.0));
+
+    members
+}
+
+/// Turns the alert of a check into an alert for its whole group, which
+/// carries the group name as check ID and lists the checks of the group in
+/// its labels.
+fn group_alert(group: &str, members: &[(String, CheckStatus)], alert: Alert) -> Alert {
+    let members = members.iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>().join(",");
+
+    Alert {
+        id: group.to_string(),
+        payload: None,
+        message: None,
+        labels: HashMap::from([
+            ("group".to_string(), group.to_string()),
+            ("members".to_string(), members),
+        ]),
+        ..alert
+    }
+}
+
+/// Sends `alert` to the notifiers, logging any errors. Returns the alert
+/// with its targets narrowed down to the notifiers that failed temporarily,
+/// if any, so that it can be retried.
+async fn dispatch_alert(alert: Alert, notifier: Arc<CompositeNotifier>, metrics: Arc<Metrics>) -> Option<Alert> {
+    let result = match alert.event {
+        AlertEvent::Failure => notifier.notify_failure(alert.clone()).await,
+        AlertEvent::Recovery => notifier.notify_recovery(alert.clone()).await,
+    };
+
+    let retry_targets = report_result(&alert.id, alert.event, result, &metrics);
+    retry_alert(alert, &retry_targets)
+}
+
+/// Sends a batch of failures to the notifiers at once, like
+/// `dispatch_alert`.
+async fn dispatch_batch(alerts: Vec<Alert>, notifier: Arc<CompositeNotifier>, metrics: Arc<Metrics>) -> Vec<Alert> {
+    let ids = alerts.iter().map(|a| a.id.as_str()).collect::<Vec<_>>().join(",");
+    debug!(id = ids, event = "failure"; "notifying {} batched failures", alerts.len());
+
+    let result = notifier.notify_failures(&alerts).await;
+
+    let retry_targets = report_result(&ids, AlertEvent::Failure, result, &metrics);
+    alerts.into_iter().filter_map(|alert| retry_alert(alert, &retry_targets)).collect()
+}
+
+/// Logs the outcome of a notification, and returns the names of the
+/// notifiers that failed temporarily. An empty name stands for an error that
+/// did not name a notifier.
+fn report_result(id: &str, event: AlertEvent, result: Result<(), DodemansknopError>, metrics: &Metrics) -> Vec<String> {
+    let errors = match result {
+        Ok(_) => {
+            info!(id = id, event = event.as_str(); "{} notified", event.as_str());
+            return Vec::new();
         }
-    });
+        Err(DodemansknopError::Notifiers(errors)) => errors,
+        Err(e) => vec![(String::new(), e)],
+    };
+
+    metrics.inc_notifier_errors();
+
+    let mut retry_targets = Vec::new();
+    for (name, e) in errors {
+        match e {
+            DodemansknopError::Config(_) => {
+                error!(id = id, event = event.as_str(), notifier = name; "notifier is misconfigured; {} was not delivered: {}", event.as_str(), e)
+            }
+            _ if e.is_retryable() => {
+                warn!(id = id, event = event.as_str(), notifier = name; "temporary error while notifying about {}: {}", event.as_str(), e);
+                retry_targets.push(name);
+            }
+            _ => warn!(id = id, event = event.as_str(), notifier = name; "error while notifying about {}: {}", event.as_str(), e),
+        }
+    }
+
+    retry_targets
+}
+
+/// Narrows the targets of `alert` down to those in `retry_targets`, or
+/// returns `None` if none of its targets needs to be retried.
+fn retry_alert(alert: Alert, retry_targets: &[String]) -> Option<Alert> {
+    if retry_targets.iter().any(|name| name.is_empty()) {
+        return Some(alert);
+    }
+
+    let targets: Vec<String> = retry_targets.iter()
+        .filter(|name| match alert.targets {
+            Some(ref targets) => targets.contains(name),
+            None => true,
+        })
+        .cloned()
+        .collect();
+
+    if targets.is_empty() {
+        None
+    } else {
+        Some(Alert { targets: Some(targets), ..alert })
+    }
 }
 
-async fn serve_api(listen_addr: String, tx_ping: SyncSender<String>) {
-    let api = filters::routes(tx_ping);
-    let routes = api.with(warp::log("ping"));
+/// Records the outcome of a dispatch in the retry queue. `key` identifies
+/// the queue entry if the alert was a retry; `retry` are the alerts that
+/// failed temporarily.
+fn record_dispatch(retry_queue: &mut Option<RetryQueue>, key: Option<String>, mut retry: Vec<Alert>) {
+    let queue = match retry_queue {
+        Some(q) => q,
+        None => return,
+    };
+
+    match key {
+        None => retry.into_iter().for_each(|alert| queue.push(alert)),
+        Some(key) => match retry.pop() {
+            Some(alert) => queue.reschedule(&key, alert),
+            None => queue.complete(&key),
+        },
+    }
+}
 
-    let addr: SocketAddr = listen_addr.parse().unwrap();
+/// Dispatches the failures collected while batching, all at once. A single
+/// failure is dispatched like any other alert.
+fn flush_batch(batch: &mut Vec<Alert>, notified: &mut HashSet<String>, dispatcher: &mut Dispatcher) {
+    let mut alerts = std::mem::take(batch);
+    notified.extend(alerts.iter().map(|a| a.id.clone()));
 
-    warp::serve(routes).run(addr).await;
-    return;
+    match alerts.len() {
+        0 => {},
+        1 => dispatcher.dispatch(None, alerts.remove(0)),
+        _ => dispatcher.dispatch_batch(alerts),
+    }
 }
 
-mod filters {
-    use std::convert::Infallible;
-    use std::sync::mpsc::SyncSender;
+/// Outcome of a dispatch: the key of the retry queue entry it was sent for
+/// (if any), and the alerts that failed temporarily.
+type DispatchOutcome = (Option<String>, Vec<Alert>);
 
-    use warp::Filter;
+/// Longest time a notification waits behind ones of higher severity; once
+/// it waited this long, it is sent next regardless of its severity.
+const MAX_PRIORITY_DELAY: Duration = Duration::from_secs(30);
 
-    use super::handlers;
+/// A notification waiting for a free slot.
+struct PendingDispatch {
+    seq: u64,
+    queued_at: Instant,
+    dispatch: BoxFuture<'static, DispatchOutcome>,
+}
+
+/// Runs each notification in its own task, so that a slow notifier does not
+/// hold up alerts for other checks. At most `notifier_concurrency`
+/// notifications are in flight at once; since they run concurrently, they
+/// may complete in any order.
+///
+/// Notifications waiting for a free slot are started by severity, so that
+/// during an alert storm critical alerts are not queued up behind lots of
+/// informational ones; within a severity, they are started in order. Those
+/// waiting longer than `MAX_PRIORITY_DELAY` are started first, so that no
+/// notification is held back indefinitely.
+struct Dispatcher {
+    notifier: SharedNotifier,
+    metrics: Arc<Metrics>,
+    concurrency: usize,
+    tasks: JoinSet<DispatchOutcome>,
+
+    /// Waiting notifications, one queue per severity from least to most
+    /// urgent.
+    pending: [VecDeque<PendingDispatch>; 3],
+    next_seq: u64,
+}
 
-    pub fn routes(tx_ping: SyncSender<String>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
-        ping(tx_ping).or(health())
+impl Dispatcher {
+    fn new(notifier: SharedNotifier, metrics: Arc<Metrics>, concurrency: usize) -> Self {
+        Self {
+            notifier,
+            metrics,
+            concurrency,
+            tasks: JoinSet::new(),
+            pending: Default::default(),
+            next_seq: 0,
+        }
     }
 
-    pub fn ping(ping_tx: SyncSender<String>) -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
-        warp::path!("ping" / String)
-            .and(warp::post())
-            .and(with_ping_tx(ping_tx))
-            .and_then(handlers::ping)
+    fn dispatch(&mut self, key: Option<String>, alert: Alert) {
+        let severity = alert.severity;
+        self.spawn(severity, move |n, metrics| async move {
+            (key, dispatch_alert(alert, n, metrics).await.into_iter().collect())
+        });
     }
 
-    pub fn health() -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
-        warp::path!("health")
-            .and(warp::get())
-            .and_then(handlers::health)
+    fn dispatch_batch(&mut self, alerts: Vec<Alert>) {
+        let severity = alerts.iter().map(|a| a.severity).max().unwrap_or_default();
+        self.spawn(severity, move |n, metrics| async move {
+            (None, dispatch_batch(alerts, n, metrics).await)
+        });
     }
 
-    fn with_ping_tx(tx: SyncSender<String>) -> impl Filter<Extract=(SyncSender<String>, ), Error=Infallible> + Clone {
-        warp::any().map(move || tx.clone())
+    fn spawn<F, Fut>(&mut self, severity: Severity, f: F)
+        where F: FnOnce(Arc<CompositeNotifier>, Arc<Metrics>) -> Fut,
+              Fut: std::future::Future<Output = DispatchOutcome> + Send + 'static
+    {
+        // The notifier is picked now, so that alerts raised before a reload
+        // are sent with the notifiers that were configured at that time.
+        let dispatch = f(self.notifier.read().unwrap().clone(), self.metrics.clone()).boxed();
+
+        self.pending[severity as usize].push_back(PendingDispatch {
+            seq: self.next_seq,
+            queued_at: Instant::now(),
+            dispatch,
+        });
+        self.next_seq += 1;
+
+        self.start_pending();
+    }
+
+    /// Starts waiting notifications while there are free slots.
+    fn start_pending(&mut self) {
+        while self.tasks.len() < self.concurrency {
+            match self.next_pending() {
+                Some(pending) => {
+                    self.tasks.spawn(pending.dispatch);
+                },
+                None => break,
+            }
+        }
+    }
+
+    /// Takes the notification to start next: the one waiting longest if it
+    /// exceeded `MAX_PRIORITY_DELAY`, otherwise the oldest one of the highest
+    /// severity.
+    fn next_pending(&mut self) -> Option<PendingDispatch> {
+        let oldest = self.pending.iter()
+            .enumerate()
+            .filter_map(|(i, queue)| queue.front().map(|p| (i, p)))
+            .min_by_key(|(_, p)| p.seq);
+
+        let queue = match oldest {
+            Some((i, p)) if p.queued_at.elapsed() >= MAX_PRIORITY_DELAY => i,
+            _ => self.pending.iter().rposition(|queue| !queue.is_empty())?,
+        };
+
+        self.pending[queue].pop_front()
+    }
+
+    /// Waits for a running notification to complete, and starts the next
+    /// waiting one in its slot.
+    async fn join_next(&mut self) -> Option<Result<DispatchOutcome, tokio::task::JoinError>> {
+        let result = self.tasks.join_next().await;
+        self.start_pending();
+        result
+    }
+
+    /// Number of notifications that are running or waiting.
+    fn len(&self) -> usize {
+        self.tasks.len() + self.pending.iter().map(|queue| queue.len()).sum::<usize>()
     }
 }
 
-mod handlers {
-    use std::convert::Infallible;
-    use std::sync::mpsc::SyncSender;
+async fn sleep_until_deadline(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => futures::future::pending().await,
+    }
+}
 
-    use log::warn;
-    use warp::http::StatusCode;
+/// Reloads the configuration file whenever the process receives SIGHUP, and
+/// hands the new settings and notifiers to the ping receiver, which applies
+/// both at once, and the new settings to the alerter. A configuration that
+/// fails to load is rejected, keeping the previous one.
+#[cfg(unix)]
+fn run_reload_task(config_file: Option<String>, tx_commands: mpsc::UnboundedSender<Command>, tx_settings: watch::Sender<Arc<Settings>>, clock: Arc<dyn Clock>) {
+    use tokio::signal::unix::{signal, SignalKind};
 
-    pub async fn ping(id: String, tx: SyncSender<String>) -> Result<impl warp::Reply, Infallible> {
-        match tx.send(id) {
-            Ok(_) => Ok(StatusCode::OK),
-            Err(err) => {
-                warn!("error while sending ping to handler thread: {}", err);
-                Ok(StatusCode::SERVICE_UNAVAILABLE)
+    let mut hangup = match signal(SignalKind::hangup()) {
+        Ok(s) => s,
+        Err(e) => {
+            warn!("error while listening for SIGHUP; configuration reloading is disabled: {}", e);
+            return;
+        }
+    };
+
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            info!("received SIGHUP; reloading configuration");
+
+            let settings = match config::retrieve_settings(config_file.clone()) {
+                Ok(s) => s,
+                Err(e) => {
+                    error!("could not reload configuration; keeping previous configuration: {}", e);
+                    continue;
+                }
+            };
+
+            if let Err(problems) = settings.validate() {
+                error!("invalid configuration; keeping previous configuration:{}", format_problems(&problems));
+                continue;
+            }
+
+            for deprecation in settings.deprecations() {
+                warn!("{}", deprecation);
             }
+
+            let notifier = match build_notifier_set(&settings, &clock) {
+                Ok(n) => n,
+                Err(e) => {
+                    error!("could not reload configuration; keeping previous configuration: {}", e);
+                    continue;
+                }
+            };
+
+            let cmd = Command::Reload { settings: Box::new(settings.clone()), notifier: Arc::new(notifier) };
+            if tx_commands.send(cmd).is_err() {
+                break;
+            }
+
+            // Fails only once the alerter stopped, on shutdown.
+            let _ = tx_settings.send(Arc::new(settings));
+        }
+    });
+}
+
+#[cfg(not(unix))]
+fn run_reload_task(_config_file: Option<String>, _tx_commands: mpsc::UnboundedSender<Command>, _tx_settings: watch::Sender<Arc<Settings>>, _clock: Arc<dyn Clock>) {}
+
+/// Serves the API on all configured listeners until shutdown.
+async fn serve_api(listen_addrs: Vec<(SocketAddr, RouteSet)>, tx_ping: mpsc::Sender<Ping>, tx_commands: mpsc::UnboundedSender<Command>, health: Arc<Health>, metrics: Arc<Metrics>, check_state: SharedState, settings: &Settings) {
+    let auth_token = settings.auth_token();
+    let policy = filters::ping_policy(settings, check_state.clone(), health.clone());
+
+    // A single shutdown signal stops all listeners at once.
+    let shutdown = shutdown_signal().boxed().shared();
+
+    let servers = listen_addrs.into_iter().map(|(addr, set)| {
+        let ping = || filters::ping_routes(tx_ping.clone(), metrics.clone(), auth_token.clone(), settings, policy.clone());
+        let admin = || filters::admin_routes(tx_commands.clone(), health.clone(), metrics.clone(), auth_token.clone(), settings.server.allow_admin_reset);
+
+        match set {
+            RouteSet::All => serve_listener(ping().or(admin()), addr, set, settings, shutdown.clone()),
+            RouteSet::Ping => serve_listener(ping().or(filters::probe_routes(health.clone())), addr, set, settings, shutdown.clone()),
+            RouteSet::Admin => serve_listener(admin(), addr, set, settings, shutdown.clone()),
         }
+    }).collect::<Vec<_>>();
+
+    // The ping sender has to be dropped along with the servers, so that the
+    // ping receiver stops once they have.
+    drop(tx_ping);
+
+    futures::future::join_all(servers).await;
+}
+
+fn serve_listener<'a, F>(routes: F, listen_addr: SocketAddr, set: RouteSet, settings: &'a Settings, shutdown: Shared<BoxFuture<'static, ()>>) -> BoxFuture<'a, ()>
+    where F: Filter<Error = warp::Rejection> + Clone + Send + Sync + 'static, F::Extract: warp::Reply
+{
+    let routes = routes
+        .recover(handlers::handle_rejection)
+        .with(warp::log("ping"));
+
+    match settings.cors() {
+        Some(cors) => serve_routes(routes.with(cors_filter(cors)), listen_addr, set, settings, shutdown).boxed(),
+        None => serve_routes(routes, listen_addr, set, settings, shutdown).boxed(),
     }
+}
 
-    pub async fn health() -> Result<impl warp::Reply, Infallible> {
-        Ok(StatusCode::OK)
+/// Builds the CORS filter, which also answers preflight requests. The
+/// settings are validated, so the builder does not panic on them.
+fn cors_filter(cors: &CorsSettings) -> warp::cors::Builder {
+    let builder = warp::cors()
+        .allow_methods(cors.allowed_methods.iter().map(|m| m.as_str()))
+        .allow_headers(cors.allowed_headers.iter().map(|h| h.as_str()))
+        .expose_headers(["x-request-id"]);
+
+    if cors.allowed_origins.iter().any(|o| o == "*") {
+        builder.allow_any_origin()
+    } else {
+        builder.allow_origins(cors.allowed_origins.iter().map(|o| o.as_str()))
+    }
+}
+
+async fn serve_routes<F>(routes: F, listen_addr: SocketAddr, set: RouteSet, settings: &Settings, shutdown: Shared<BoxFuture<'static, ()>>)
+    where F: Filter<Error = warp::Rejection> + Clone + Send + Sync + 'static, F::Extract: warp::Reply
+{
+    match settings.tls_paths() {
+        Some((cert_path, key_path)) => {
+            let (addr, server) = warp::serve(routes)
+                .tls()
+                .cert_path(cert_path)
+                .key_path(key_path)
+                .bind_with_graceful_shutdown(listen_addr, shutdown);
+
+            info!("listening on {} for {} routes (TLS)", addr, set.as_str());
+
+            server.await;
+        },
+        None => {
+            let (addr, server) = warp::serve(routes)
+                .bind_with_graceful_shutdown(listen_addr, shutdown);
+
+            info!("listening on {} for {} routes", addr, set.as_str());
+
+            server.await;
+        },
     }
-}
\ No newline at end of file
+}
+
+/// Resolves as soon as the process receives either SIGINT or SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("error while listening for SIGINT: {}", e);
+            futures::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut s) => { s.recv().await; },
+            Err(e) => {
+                warn!("error while listening for SIGTERM: {}", e);
+                futures::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = futures::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("received SIGINT; stopping http server"),
+        _ = terminate => info!("received SIGTERM; stopping http server"),
+    }
+}
+
+mod filters {
+    use std::convert::Infallible;
+    use std::sync::Arc;
+
+    use tokio::sync::mpsc::{Sender, UnboundedSender};
+
+    use warp::hyper::body::Bytes;
+    use warp::Filter;
+
+    use crate::config::Settings;
+    use crate::health::Health;
+    use crate::metrics::Metrics;
+    use crate::ratelimit::{Debouncer, RateLimiter};
+    use crate::scheduler::{Command, Ping};
+    use crate::state::SharedState;
+
+    use super::handlers::{self, PingPolicy};
+
+    /// Upper bound for the size of a bulk ping request body.
+    const MAX_BULK_PING_BODY_BYTES: u64 = 64 * 1024;
+
+    /// Upper bound for the size of a ping's payload.
+    const MAX_PING_PAYLOAD_BYTES: u64 = 16 * 1024;
+
+    /// Rejection for requests that lack a valid authentication token.
+    #[derive(Debug)]
+    pub struct Unauthorized;
+
+    impl warp::reject::Reject for Unauthorized {}
+
+    /// Builds the rules for pings, which are shared by all listeners that
+    /// serve pings, so that rate limits apply across them.
+    pub fn ping_policy(settings: &Settings, check_state: SharedState, worker_health: Arc<Health>) -> Arc<PingPolicy> {
+        let server = &settings.server;
+        Arc::new(PingPolicy {
+            id_pattern: server.check_id_regex().expect("check_id_pattern is validated with the settings"),
+            limiter: RateLimiter::new(server.ping_rate_limit),
+            debouncer: Debouncer::new(server.ping_debounce()),
+            enqueue_timeout: server.ping_enqueue_timeout(),
+            max_checks: server.max_checks,
+            declared: settings.declared_ids().cloned().collect(),
+            check_state,
+            json_response: server.ping_json_response,
+            health: worker_health,
+        })
+    }
+
+    /// Routes for sending pings.
+    pub fn ping_routes(tx_ping: Sender<Ping>, metrics: Arc<Metrics>, auth_token: Option<String>, settings: &Settings, policy: Arc<PingPolicy>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        let server = &settings.server;
+
+        ping(tx_ping.clone(), metrics.clone(), auth_token.clone(), server.allow_get_ping, policy.clone())
+            .or(bulk_ping(tx_ping, metrics, auth_token, server.max_ping_batch_size, policy))
+    }
+
+    /// Routes for liveness and readiness probes, which every listener
+    /// serves.
+    pub fn probe_routes(worker_health: Arc<Health>) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        health().or(healthz(worker_health))
+    }
+
+    /// Routes for inspecting and managing checks, along with the probes,
+    /// metrics and API description.
+    pub fn admin_routes(tx_commands: UnboundedSender<Command>, worker_health: Arc<Health>, metrics: Arc<Metrics>, auth_token: Option<String>, allow_reset: bool) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        probe_routes(worker_health)
+            .or(version())
+            .or(openapi())
+            .or(metrics_route(metrics))
+            .or(list_checks(tx_commands.clone()))
+            .or(get_check(tx_commands.clone()))
+            .or(list_events(tx_commands.clone()))
+            .or(pause_check(tx_commands.clone(), auth_token.clone()))
+            .or(resume_check(tx_commands.clone(), auth_token.clone()))
+            .or(acknowledge_check(tx_commands.clone(), auth_token.clone()))
+            .or(delete_check(tx_commands.clone(), auth_token.clone()))
+            .or(test_notification(tx_commands.clone(), auth_token.clone()))
+            .or(reset(tx_commands, auth_token, allow_reset))
+    }
+
+    pub fn ping(ping_tx: Sender<Ping>, metrics: Arc<Metrics>, auth_token: Option<String>, allow_get: bool, policy: Arc<PingPolicy>) -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("ping" / String)
+            .and(ping_method(allow_get))
+            .and(authorized(auth_token))
+            .and(warp::header::optional::<String>("x-request-id"))
+            .and(warp::header::optional::<String>("accept"))
+            .and(optional_body(MAX_PING_PAYLOAD_BYTES))
+            .and(with_ping_tx(ping_tx))
+            .and(with_metrics(metrics))
+            .and(warp::any().map(move || policy.clone()))
+            .and_then(handlers::ping)
+    }
+
+    pub fn bulk_ping(ping_tx: Sender<Ping>, metrics: Arc<Metrics>, auth_token: Option<String>, max_batch_size: usize, policy: Arc<PingPolicy>) -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("ping")
+            .and(warp::post())
+            .and(authorized(auth_token))
+            .and(warp::header::optional::<String>("x-request-id"))
+            .and(warp::body::content_length_limit(MAX_BULK_PING_BODY_BYTES))
+            .and(warp::body::json())
+            .and(with_ping_tx(ping_tx))
+            .and(with_metrics(metrics))
+            .and(warp::any().map(move || max_batch_size))
+            .and(warp::any().map(move || policy.clone()))
+            .and_then(handlers::bulk_ping)
+    }
+
+    /// Extracts the request body, limited to `limit` bytes. Unlike
+    /// `warp::body::content_length_limit`, requests without a Content-Length
+    /// header are accepted, and are treated as having no body.
+    fn optional_body(limit: u64) -> impl Filter<Extract=(Bytes, ), Error=warp::Rejection> + Clone {
+        let without_body = warp::header::optional::<u64>("content-length")
+            .and_then(|length: Option<u64>| async move {
+                match length {
+                    None => Ok(Bytes::new()),
+                    Some(_) => Err(warp::reject::not_found()),
+                }
+            });
+
+        without_body
+            .or(warp::body::content_length_limit(limit).and(warp::body::bytes()))
+            .unify()
+    }
+
+    /// Matches POST requests, and GET requests if `allow_get` is set. When
+    /// GET is not allowed, it is rejected with 405 like any other method.
+    fn ping_method(allow_get: bool) -> impl Filter<Extract=(), Error=warp::Rejection> + Clone {
+        let get = warp::get()
+            .and_then(move || async move {
+                if allow_get {
+                    Ok(())
+                } else {
+                    Err(warp::reject::not_found())
+                }
+            })
+            .untuple_one();
+
+        warp::post().or(get).unify()
+    }
+
+    pub fn metrics_route(metrics: Arc<Metrics>) -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("metrics")
+            .and(warp::get())
+            .and(with_metrics(metrics))
+            .and_then(handlers::metrics)
+    }
+
+    pub fn health() -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("health")
+            .and(warp::get())
+            .and_then(handlers::health)
+    }
+
+    /// Unauthenticated, like `health`, as it reveals nothing about checks.
+    pub fn version() -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("version")
+            .and(warp::get())
+            .and_then(handlers::version)
+    }
+
+    /// Unauthenticated, so that clients can be generated without a token.
+    pub fn openapi() -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("openapi.json")
+            .and(warp::get())
+            .and_then(handlers::openapi)
+    }
+
+    /// Readiness probe; unlike `ping`, this never touches any check.
+    pub fn healthz(health: Arc<Health>) -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("healthz")
+            .and(warp::get())
+            .and(warp::any().map(move || health.clone()))
+            .and_then(handlers::healthz)
+    }
+
+    pub fn list_checks(tx_commands: UnboundedSender<Command>) -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("checks")
+            .and(warp::get())
+            .and(with_commands_tx(tx_commands))
+            .and_then(handlers::list_checks)
+    }
+
+    pub fn get_check(tx_commands: UnboundedSender<Command>) -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("checks" / String)
+            .and(warp::get())
+            .and(with_commands_tx(tx_commands))
+            .and_then(handlers::get_check)
+    }
+
+    pub fn list_events(tx_commands: UnboundedSender<Command>) -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("events")
+            .and(warp::get())
+            .and(warp::query::<handlers::EventQuery>())
+            .and(with_commands_tx(tx_commands))
+            .and_then(handlers::list_events)
+    }
+
+    pub fn pause_check(tx_commands: UnboundedSender<Command>, auth_token: Option<String>) -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("checks" / String / "pause")
+            .and(warp::post())
+            .and(authorized(auth_token))
+            .and(with_commands_tx(tx_commands))
+            .and_then(handlers::pause_check)
+    }
+
+    pub fn resume_check(tx_commands: UnboundedSender<Command>, auth_token: Option<String>) -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("checks" / String / "resume")
+            .and(warp::post())
+            .and(authorized(auth_token))
+            .and(with_commands_tx(tx_commands))
+            .and_then(handlers::resume_check)
+    }
+
+    pub fn acknowledge_check(tx_commands: UnboundedSender<Command>, auth_token: Option<String>) -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("checks" / String / "ack")
+            .and(warp::post())
+            .and(authorized(auth_token))
+            .and(with_commands_tx(tx_commands))
+            .and_then(handlers::acknowledge_check)
+    }
+
+    pub fn delete_check(tx_commands: UnboundedSender<Command>, auth_token: Option<String>) -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("checks" / String)
+            .and(warp::delete())
+            .and(authorized(auth_token))
+            .and(with_commands_tx(tx_commands))
+            .and_then(handlers::delete_check)
+    }
+
+    pub fn test_notification(tx_commands: UnboundedSender<Command>, auth_token: Option<String>) -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("test-notification")
+            .and(warp::post())
+            .and(authorized(auth_token))
+            .and(with_commands_tx(tx_commands))
+            .and_then(handlers::test_notification)
+    }
+
+    pub fn reset(tx_commands: UnboundedSender<Command>, auth_token: Option<String>, allowed: bool) -> impl Filter<Extract=impl warp::Reply, Error=warp::Rejection> + Clone {
+        warp::path!("admin" / "reset")
+            .and(warp::post())
+            .and(authorized(auth_token))
+            .and(with_commands_tx(tx_commands))
+            .and(warp::any().map(move || allowed))
+            .and_then(handlers::reset)
+    }
+
+    /// Rejects requests with `Unauthorized` unless they carry the bearer token
+    /// `token`. If no token is configured, all requests are let through.
+    pub fn authorized(token: Option<String>) -> impl Filter<Extract=(), Error=warp::Rejection> + Clone {
+        warp::header::optional::<String>("authorization")
+            .and_then(move |header: Option<String>| {
+                let token = token.clone();
+                async move {
+                    let expected = match token {
+                        Some(t) => t,
+                        None => return Ok(()),
+                    };
+
+                    let given = header.as_deref()
+                        .and_then(|h| h.strip_prefix("Bearer "))
+                        .unwrap_or("");
+
+                    if constant_time_eq(given.as_bytes(), expected.as_bytes()) {
+                        Ok(())
+                    } else {
+                        Err(warp::reject::custom(Unauthorized))
+                    }
+                }
+            })
+            .untuple_one()
+    }
+
+    /// Compares two byte strings in time that depends only on their lengths,
+    /// not on their contents.
+    fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+        if a.len() != b.len() {
+            return false;
+        }
+
+        a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+    }
+
+    fn with_ping_tx(tx: Sender<Ping>) -> impl Filter<Extract=(Sender<Ping>, ), Error=Infallible> + Clone {
+        warp::any().map(move || tx.clone())
+    }
+
+    fn with_commands_tx(tx: UnboundedSender<Command>) -> impl Filter<Extract=(UnboundedSender<Command>, ), Error=Infallible> + Clone {
+        warp::any().map(move || tx.clone())
+    }
+
+    fn with_metrics(metrics: Arc<Metrics>) -> impl Filter<Extract=(Arc<Metrics>, ), Error=Infallible> + Clone {
+        warp::any().map(move || metrics.clone())
+    }
+}
+
+mod handlers {
+    use std::collections::HashSet;
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use chrono::{DateTime, Utc};
+    use log::{debug, error, warn};
+    use regex::Regex;
+    use warp::hyper::body::Bytes;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    use tokio::sync::mpsc::{Sender, UnboundedSender};
+    use tokio::sync::mpsc::error::{SendTimeoutError, TrySendError};
+    use tokio::time::Instant;
+    use tokio::sync::oneshot;
+    use warp::http::StatusCode;
+    use warp::Rejection;
+
+    use crate::error::DodemansknopError;
+    use crate::health::{Health, Worker};
+    use crate::metrics::Metrics;
+    use crate::ratelimit::{Debouncer, RateLimiter};
+    use crate::scheduler::{Command, Ping};
+    use crate::state::{CheckInfo, CheckSource, SharedState};
+
+    /// Rules applied to pings before they are handed to the ping receiver.
+    pub struct PingPolicy {
+        /// Pattern that check IDs have to match, so that IDs with slashes
+        /// or control characters do not end up in the state and the log.
+        pub id_pattern: Regex,
+
+        pub limiter: RateLimiter,
+
+        /// Collapses pings that repeat an accepted one within a short time.
+        pub debouncer: Debouncer,
+
+        /// Maximum time a ping waits for room in a full ping queue.
+        pub enqueue_timeout: Duration,
+
+        /// Maximum number of discovered checks; see `ServerSettings`.
+        pub max_checks: Option<usize>,
+
+        /// IDs of the declared checks, which are exempt from `max_checks`.
+        pub declared: HashSet<String>,
+
+        pub check_state: SharedState,
+
+        /// Whether successful pings are always answered with the check's
+        /// next deadline, instead of only if JSON is accepted.
+        pub json_response: bool,
+
+        /// Pings are rejected once a worker died, as a missed ping would no
+        /// longer raise an alert.
+        pub health: Arc<Health>,
+    }
+
+    impl PingPolicy {
+        /// Decides whether a ping for `id`, whose ID already matched the
+        /// pattern, may be enqueued, or the status it is rejected with: 429
+        /// if it exceeds the rate limit, then 507 if it would exceed
+        /// `max_checks`. Single and bulk pings are admitted alike.
+        pub fn admit(&self, id: &str, request_id: &str) -> Result<(), StatusCode> {
+            if !self.limiter.check(id) {
+                debug!(id = id, request_id = request_id; "rate limit exceeded; dropping ping for {} (request {})", id, request_id);
+                return Err(StatusCode::TOO_MANY_REQUESTS);
+            }
+
+            if self.exceeds_max_checks(id) {
+                warn!(id = id, request_id = request_id; "maximum number of checks reached; rejecting ping for unknown check {} (request {})", id, request_id);
+                return Err(StatusCode::INSUFFICIENT_STORAGE);
+            }
+
+            Ok(())
+        }
+
+        /// Whether a ping for `id` has to be rejected because it would start
+        /// tracking a new check beyond `max_checks`. The ping receiver
+        /// enforces the limit as well; this only lets the client know.
+        fn exceeds_max_checks(&self, id: &str) -> bool {
+            let max_checks = match self.max_checks {
+                Some(max) if !self.declared.contains(id) => max,
+                _ => return false,
+            };
+
+            let checks = self.check_state.lock().unwrap();
+            if checks.contains_key(id) {
+                return false;
+            }
+
+            let discovered = checks.values().filter(|c| c.source == CheckSource::Discovered).count();
+            discovered >= max_checks
+        }
+    }
+
+    /// Body of a bulk ping request.
+    #[derive(Deserialize)]
+    pub struct BulkPing {
+        pub ids: Vec<String>,
+    }
+
+    /// Query parameters of `GET /events`.
+    #[derive(Deserialize)]
+    pub struct EventQuery {
+        pub id: Option<String>,
+
+        #[serde(default = "default_event_limit")]
+        pub limit: usize,
+    }
+
+    fn default_event_limit() -> usize {
+        100
+    }
+
+    #[derive(Serialize)]
+    struct BulkPingResult {
+        id: String,
+        accepted: bool,
+    }
+
+    /// Handles a ping. The request ID sent by the client in `X-Request-Id`,
+    /// or a newly generated one, is passed along with the ping so that log
+    /// lines of the ping receiver can be correlated with the request, and is
+    /// returned in the response.
+    ///
+    /// A non-empty request body is stored as the check's last payload and
+    /// included in a subsequent failure alert.
+    ///
+    /// Pings for IDs that do not match the configured pattern are rejected
+    /// with 400. Pings exceeding the per-check rate limit are rejected with
+    /// 429 and not enqueued; since only the latest ping matters, nothing is
+    /// lost. Pings that would exceed `max_checks` are rejected with 507.
+    ///
+    /// Clients that accept `application/json` (or all clients, if
+    /// `ping_json_response` is set) get the check's next deadline as a Unix
+    /// timestamp; it is `null` if the check is paused or not expected to ping
+    /// again. Others get an empty body.
+    pub async fn ping(id: String, request_id: Option<String>, accept: Option<String>, body: Bytes, tx: Sender<Ping>, metrics: Arc<Metrics>, policy: Arc<PingPolicy>) -> Result<Box<dyn warp::Reply>, Infallible> {
+        let request_id = request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        if !policy.health.is_ready() {
+            debug!(request_id = request_id; "still starting; rejecting ping (request {})", request_id);
+            return Ok(with_request_id(StatusCode::SERVICE_UNAVAILABLE, request_id));
+        }
+
+        if let Some(worker) = policy.health.dead_worker() {
+            warn!(request_id = request_id; "{} is not running; rejecting ping (request {})", worker.as_str(), request_id);
+            return Ok(with_request_id(StatusCode::SERVICE_UNAVAILABLE, request_id));
+        }
+
+        if !policy.id_pattern.is_match(&id) {
+            debug!(request_id = request_id; "rejecting ping for invalid check id {:?} (request {})", id, request_id);
+            return Ok(with_request_id(StatusCode::BAD_REQUEST, request_id));
+        }
+
+        if let Err(status) = policy.admit(&id, &request_id) {
+            return Ok(with_request_id(status, request_id));
+        }
+
+        // The first ping already reset the timer, so duplicates need not be
+        // processed; their payload is dropped.
+        if policy.debouncer.is_duplicate(&id) {
+            debug!(id = id, request_id = request_id; "ignoring duplicate ping for {} (request {})", id, request_id);
+            return Ok(with_request_id(StatusCode::OK, request_id));
+        }
+
+        let payload = if body.is_empty() {
+            None
+        } else {
+            Some(String::from_utf8_lossy(&body).into_owned())
+        };
+
+        let deadline = Instant::now() + policy.enqueue_timeout;
+
+        // The next deadline is only known once the ping receiver processed
+        // the ping, so the response waits for it only if it is wanted.
+        let wants_json = policy.json_response || accept.as_deref().is_some_and(accepts_json);
+        let (reply_tx, reply_rx) = match wants_json {
+            true => {
+                let (tx, rx) = oneshot::channel();
+                (Some(tx), Some(rx))
+            },
+            false => (None, None),
+        };
+
+        if let Err(e) = enqueue_ping(&tx, &metrics, id.clone(), &request_id, payload, reply_tx, deadline).await {
+            policy.debouncer.forget(&id);
+            return Ok(with_request_id(e.status(&policy.health), request_id));
+        }
+
+        match reply_rx {
+            Some(rx) => {
+                let next_deadline = rx.await.ok().flatten().map(|d| d.timestamp());
+                Ok(with_request_id(warp::reply::json(&json!({ "id": id, "next_deadline": next_deadline })), request_id))
+            },
+            None => Ok(with_request_id(StatusCode::OK, request_id)),
+        }
+    }
+
+    fn with_request_id<T: warp::Reply + 'static>(reply: T, request_id: String) -> Box<dyn warp::Reply> {
+        Box::new(warp::reply::with_header(reply, "x-request-id", request_id))
+    }
+
+    /// Whether an `Accept` header lists `application/json`.
+    fn accepts_json(accept: &str) -> bool {
+        accept.split(',')
+            .filter_map(|media_range| media_range.split(';').next())
+            .any(|media_type| media_type.trim().eq_ignore_ascii_case("application/json"))
+    }
+
+    /// Handles a ping for several checks at once. The response lists for
+    /// each ID whether its ping was accepted; if any was not, the status is
+    /// 503 so that the client retries, 507 if any exceeded `max_checks`, 429
+    /// if any exceeded the rate limit, or 500 if the ping receiver is gone.
+    /// Like single pings, each ID is subject to the rate limit and to
+    /// debouncing.
+    /// If any ID is invalid, the whole request is rejected with 400.
+    pub async fn bulk_ping(request_id: Option<String>, body: BulkPing, tx: Sender<Ping>, metrics: Arc<Metrics>, max_batch_size: usize, policy: Arc<PingPolicy>) -> Result<impl warp::Reply, Infallible> {
+        let request_id = request_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        if !policy.health.is_ready() {
+            debug!(request_id = request_id; "still starting; rejecting bulk ping (request {})", request_id);
+            let reply = warp::reply::with_status(warp::reply::json(&json!({ "error": "dodemansknop is still starting" })), StatusCode::SERVICE_UNAVAILABLE);
+            return Ok(warp::reply::with_header(reply, "x-request-id", request_id));
+        }
+
+        if let Some(worker) = policy.health.dead_worker() {
+            warn!(request_id = request_id; "{} is not running; rejecting bulk ping (request {})", worker.as_str(), request_id);
+            let reply = warp::reply::with_status(warp::reply::json(&json!({ "error": format!("{} is not running", worker.as_str()) })), StatusCode::SERVICE_UNAVAILABLE);
+            return Ok(warp::reply::with_header(reply, "x-request-id", request_id));
+        }
+
+        if body.ids.len() > max_batch_size {
+            let error = format!("at most {} ids may be pinged at once", max_batch_size);
+            let reply = warp::reply::with_status(warp::reply::json(&json!({ "error": error })), StatusCode::PAYLOAD_TOO_LARGE);
+            return Ok(warp::reply::with_header(reply, "x-request-id", request_id));
+        }
+
+        let invalid: Vec<&String> = body.ids.iter().filter(|id| !policy.id_pattern.is_match(id)).collect();
+        if !invalid.is_empty() {
+            debug!(request_id = request_id; "rejecting bulk ping with invalid check ids {:?} (request {})", invalid, request_id);
+            let reply = warp::reply::with_status(warp::reply::json(&json!({ "error": "invalid check ids", "ids": invalid })), StatusCode::BAD_REQUEST);
+            return Ok(warp::reply::with_header(reply, "x-request-id", request_id));
+        }
+
+        // All pings of the batch share one deadline, so that a full queue
+        // does not hold up the request for the timeout once per ID.
+        let deadline = Instant::now() + policy.enqueue_timeout;
+
+        let mut results = Vec::with_capacity(body.ids.len());
+        let mut exceeded = false;
+        let mut limited = false;
+        let mut closed = false;
+        for id in body.ids {
+            if let Err(status) = policy.admit(&id, &request_id) {
+                exceeded |= status == StatusCode::INSUFFICIENT_STORAGE;
+                limited |= status == StatusCode::TOO_MANY_REQUESTS;
+                results.push(BulkPingResult { id, accepted: false });
+                continue;
+            }
+
+            if policy.debouncer.is_duplicate(&id) {
+                debug!(id = id, request_id = request_id; "ignoring duplicate ping for {} (request {})", id, request_id);
+                results.push(BulkPingResult { id, accepted: true });
+                continue;
+            }
+
+            let result = enqueue_ping(&tx, &metrics, id.clone(), &request_id, None, None, deadline).await;
+            if result.is_err() {
+                policy.debouncer.forget(&id);
+            }
+
+            closed |= result == Err(EnqueueError::Closed);
+            results.push(BulkPingResult { id, accepted: result.is_ok() });
+        }
+
+        let status = if closed {
+            EnqueueError::Closed.status(&policy.health)
+        } else if exceeded {
+            StatusCode::INSUFFICIENT_STORAGE
+        } else if limited {
+            StatusCode::TOO_MANY_REQUESTS
+        } else if results.iter().all(|r| r.accepted) {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+
+        let reply = warp::reply::with_status(warp::reply::json(&results), status);
+        Ok(warp::reply::with_header(reply, "x-request-id", request_id))
+    }
+
+    /// Why a ping could not be handed to the ping receiver.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum EnqueueError {
+        /// The queue stayed full until the deadline, which is transient.
+        Full,
+
+        /// The ping receiver dropped its end of the queue, so no ping is
+        /// processed anymore.
+        Closed,
+    }
+
+    impl EnqueueError {
+        /// Returns the response status for the error. A closed queue means
+        /// that the ping receiver is gone for good, so it is marked as dead
+        /// for the readiness probe to fail.
+        fn status(&self, health: &Health) -> StatusCode {
+            match self {
+                EnqueueError::Full => StatusCode::SERVICE_UNAVAILABLE,
+                EnqueueError::Closed => {
+                    health.mark_dead(Worker::PingReceiver);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                },
+            }
+        }
+    }
+
+    /// Hands a ping to the ping receiver.
+    ///
+    /// If the queue is full, the ping waits for room until `deadline`; as
+    /// the ping receiver only resets timers, a short burst usually clears
+    /// within this time. Only then the ping is dropped, so that a lasting
+    /// backlog results in 503 responses instead of piling up waiting
+    /// requests.
+    async fn enqueue_ping(tx: &Sender<Ping>, metrics: &Metrics, id: String, request_id: &str, payload: Option<String>, reply: Option<oneshot::Sender<Option<DateTime<Utc>>>>, deadline: Instant) -> Result<(), EnqueueError> {
+        metrics.inc_pings_received();
+
+        let ping = Ping { id, request_id: request_id.to_string(), payload, reply };
+
+        let ping = match tx.try_send(ping) {
+            Ok(_) => return Ok(()),
+            Err(TrySendError::Full(ping)) => ping,
+            Err(TrySendError::Closed(ping)) => {
+                error!(id = ping.id, request_id = request_id; "ping receiver is gone; rejecting ping for {} (request {})", ping.id, request_id);
+                return Err(EnqueueError::Closed);
+            }
+        };
+
+        debug!(id = ping.id, request_id = ping.request_id; "ping queue is full; waiting for room for ping for {} (request {})", ping.id, ping.request_id);
+
+        match tx.send_timeout(ping, deadline.saturating_duration_since(Instant::now())).await {
+            Ok(_) => Ok(()),
+            Err(SendTimeoutError::Timeout(ping)) => {
+                metrics.inc_pings_dropped();
+                warn!(id = ping.id, request_id = ping.request_id; "ping queue is still full; dropping ping for {} (request {})", ping.id, ping.request_id);
+                Err(EnqueueError::Full)
+            }
+            Err(SendTimeoutError::Closed(ping)) => {
+                error!(id = ping.id, request_id = request_id; "ping receiver is gone; rejecting ping for {} (request {})", ping.id, request_id);
+                Err(EnqueueError::Closed)
+            }
+        }
+    }
+
+    pub async fn acknowledge_check(id: String, tx: UnboundedSender<Command>) -> Result<impl warp::Reply, Infallible> {
+        Ok(send_command(&tx, |reply| Command::Acknowledge { id, reply }).await)
+    }
+
+    pub async fn pause_check(id: String, tx: UnboundedSender<Command>) -> Result<impl warp::Reply, Infallible> {
+        Ok(send_command(&tx, |reply| Command::Pause { id, reply }).await)
+    }
+
+    pub async fn resume_check(id: String, tx: UnboundedSender<Command>) -> Result<impl warp::Reply, Infallible> {
+        Ok(send_command(&tx, |reply| Command::Resume { id, reply }).await)
+    }
+
+    pub async fn delete_check(id: String, tx: UnboundedSender<Command>) -> Result<impl warp::Reply, Infallible> {
+        Ok(send_command(&tx, |reply| Command::Delete { id, reply }).await)
+    }
+
+    /// Sends a command to the ping receiver and maps its reply to a status
+    /// code: `200` if the check was known, `404` if it was not.
+    async fn send_command<F>(tx: &UnboundedSender<Command>, build: F) -> StatusCode
+        where F: FnOnce(oneshot::Sender<bool>) -> Command
+    {
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        if let Err(err) = tx.send(build(reply_tx)) {
+            warn!("error while sending command to ping receiver: {}", err);
+            return StatusCode::SERVICE_UNAVAILABLE;
+        }
+
+        match reply_rx.await {
+            Ok(true) => StatusCode::OK,
+            Ok(false) => StatusCode::NOT_FOUND,
+            Err(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    /// Sends a synthetic failure through the configured notifiers and reports
+    /// the outcome: `200` if all of them succeeded, `502` with the error of
+    /// each failed notifier otherwise.
+    pub async fn test_notification(tx: UnboundedSender<Command>) -> Result<impl warp::Reply, Infallible> {
+        let unavailable = || warp::reply::with_status(warp::reply::json(&json!({"success": false, "error": "ping receiver is not running"})), StatusCode::SERVICE_UNAVAILABLE);
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        if let Err(err) = tx.send(Command::TestNotification { reply: reply_tx }) {
+            warn!("error while sending command to ping receiver: {}", err);
+            return Ok(unavailable());
+        }
+
+        let result = match reply_rx.await {
+            Ok(r) => r,
+            Err(_) => return Ok(unavailable()),
+        };
+
+        let reply = match result {
+            Ok(_) => warp::reply::with_status(warp::reply::json(&json!({"success": true})), StatusCode::OK),
+            Err(DodemansknopError::Notifiers(errors)) => {
+                let errors: Vec<_> = errors.iter()
+                    .map(|(name, e)| json!({"notifier": name, "error": e.to_string()}))
+                    .collect();
+                warp::reply::with_status(warp::reply::json(&json!({"success": false, "errors": errors})), StatusCode::BAD_GATEWAY)
+            },
+            Err(e) => warp::reply::with_status(warp::reply::json(&json!({"success": false, "error": e.to_string()})), StatusCode::BAD_GATEWAY),
+        };
+
+        Ok(reply)
+    }
+
+    /// Forgets all checks, if `server.allow_admin_reset` is enabled, and
+    /// reports how many there were.
+    pub async fn reset(tx: UnboundedSender<Command>, allowed: bool) -> Result<impl warp::Reply, Infallible> {
+        if !allowed {
+            let error = "resetting is disabled; enable server.allow_admin_reset to allow it";
+            return Ok(warp::reply::with_status(warp::reply::json(&json!({ "error": error })), StatusCode::FORBIDDEN));
+        }
+
+        let unavailable = || warp::reply::with_status(warp::reply::json(&json!({ "error": "ping receiver is not running" })), StatusCode::SERVICE_UNAVAILABLE);
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        if let Err(err) = tx.send(Command::Reset { reply: reply_tx }) {
+            warn!("error while sending command to ping receiver: {}", err);
+            return Ok(unavailable());
+        }
+
+        match reply_rx.await {
+            Ok(cleared) => Ok(warp::reply::with_status(warp::reply::json(&json!({ "cleared": cleared })), StatusCode::OK)),
+            Err(_) => Ok(unavailable()),
+        }
+    }
+
+    pub async fn health() -> Result<impl warp::Reply, Infallible> {
+        Ok(StatusCode::OK)
+    }
+
+    /// Reports the build of this instance; commit and timestamp are captured
+    /// by the build script.
+    pub async fn version() -> Result<impl warp::Reply, Infallible> {
+        Ok(warp::reply::json(&json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "commit": env!("DODEMANSKNOP_GIT_COMMIT"),
+            "build_timestamp": env!("DODEMANSKNOP_BUILD_TIMESTAMP"),
+        })))
+    }
+
+    /// Serves the OpenAPI document of the HTTP API. It is maintained by
+    /// hand, so it has to be updated along with the routes.
+    pub async fn openapi() -> Result<impl warp::Reply, Infallible> {
+        Ok(warp::reply::with_header(include_str!("openapi.json"), "content-type", "application/json"))
+    }
+
+    /// Reports `503` while starting up, and once the ping receiver or the
+    /// alerter has died.
+    pub async fn healthz(health: Arc<Health>) -> Result<impl warp::Reply, Infallible> {
+        if !health.is_ready() {
+            debug!("readiness check failed: still starting");
+            return Ok(StatusCode::SERVICE_UNAVAILABLE);
+        }
+
+        if let Some(worker) = health.dead_worker() {
+            warn!("readiness check failed: {} is not running", worker.as_str());
+            return Ok(StatusCode::SERVICE_UNAVAILABLE);
+        }
+
+        Ok(StatusCode::OK)
+    }
+
+    pub async fn metrics(metrics: Arc<Metrics>) -> Result<impl warp::Reply, Infallible> {
+        Ok(warp::reply::with_header(metrics.render(), "content-type", "text/plain; version=0.0.4"))
+    }
+
+    pub async fn handle_rejection(err: Rejection) -> Result<impl warp::Reply, Rejection> {
+        if err.find::<super::filters::Unauthorized>().is_some() {
+            return Ok(warp::reply::with_header(StatusCode::UNAUTHORIZED, "www-authenticate", "Bearer"));
+        }
+
+        Err(err)
+    }
+
+    /// Lists all checks, read from the state database if there is one.
+    pub async fn list_checks(tx: UnboundedSender<Command>) -> Result<Box<dyn warp::Reply>, Infallible> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        if let Err(err) = tx.send(Command::List { reply: reply_tx }) {
+            warn!("error while sending command to ping receiver: {}", err);
+            return Ok(Box::new(StatusCode::SERVICE_UNAVAILABLE));
+        }
+
+        let checks = match reply_rx.await {
+            Ok(checks) => checks,
+            Err(_) => return Ok(Box::new(StatusCode::SERVICE_UNAVAILABLE)),
+        };
+
+        let mut list: Vec<CheckInfo> = checks.iter()
+            .map(|(id, state)| CheckInfo { id, state })
+            .collect();
+        list.sort_by_key(|c| c.id);
+
+        Ok(Box::new(warp::reply::json(&list)))
+    }
+
+    /// Describes a single check, including the time left until its deadline.
+    /// Checks that never pinged and are not declared are reported as `404`.
+    pub async fn get_check(id: String, tx: UnboundedSender<Command>) -> Result<Box<dyn warp::Reply>, Infallible> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        if let Err(err) = tx.send(Command::Describe { id, reply: reply_tx }) {
+            warn!("error while sending command to ping receiver: {}", err);
+            return Ok(Box::new(StatusCode::SERVICE_UNAVAILABLE));
+        }
+
+        match reply_rx.await {
+            Ok(Some(detail)) => Ok(Box::new(warp::reply::json(&detail))),
+            Ok(None) => Ok(Box::new(StatusCode::NOT_FOUND)),
+            Err(_) => Ok(Box::new(StatusCode::SERVICE_UNAVAILABLE)),
+        }
+    }
+
+    /// Lists the most recent events, oldest first.
+    pub async fn list_events(query: EventQuery, tx: UnboundedSender<Command>) -> Result<Box<dyn warp::Reply>, Infallible> {
+        let (reply_tx, reply_rx) = oneshot::channel();
+
+        if let Err(err) = tx.send(Command::Events { id: query.id, limit: query.limit, reply: reply_tx }) {
+            warn!("error while sending command to ping receiver: {}", err);
+            return Ok(Box::new(StatusCode::SERVICE_UNAVAILABLE));
+        }
+
+        match reply_rx.await {
+            Ok(events) => Ok(Box::new(warp::reply::json(&events))),
+            Err(_) => Ok(Box::new(StatusCode::SERVICE_UNAVAILABLE)),
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+    use serde_json::json;
+    use tokio::sync::oneshot;
+    use warp::http::StatusCode;
+
+    use super::*;
+    use crate::clock::FakeClock;
+    use crate::config::tests::settings;
+    use crate::notifier::MockNotifier;
+    use crate::scheduler::TimeoutContext;
+    use crate::state::CheckState;
+
+    /// Ping receiver and alerter sending to a mock notifier, on a fake clock
+    /// that starts on Monday, 2026-01-05 10:00 UTC and follows tokio's
+    /// paused time.
+    struct Pipeline {
+        tx_ping: mpsc::Sender<Ping>,
+        tx_commands: mpsc::UnboundedSender<Command>,
+        tx_settings: watch::Sender<Arc<Settings>>,
+        mock: MockNotifier,
+    }
+
+    impl Pipeline {
+        fn start(yaml: &str) -> Self {
+            let settings = settings(yaml);
+            let start: DateTime<Utc> = "2026-01-05T10:00:00Z".parse().unwrap();
+            let clock: Arc<dyn Clock> = Arc::new(FakeClock::new(start));
+
+            let mock = MockNotifier::default();
+            let notifier: SharedNotifier = Arc::new(RwLock::new(Arc::new(CompositeNotifier::new(vec![("mock".to_string(), Box::new(mock.clone()))]))));
+            let metrics = Arc::new(Metrics::default());
+            let check_state = state::new_shared_state();
+
+            let (tx_alert, rx_alert) = mpsc::unbounded_channel();
+            let (tx_ping, rx_ping) = mpsc::channel(16);
+            let (tx_commands, rx_commands) = mpsc::unbounded_channel();
+
+            let (tx_settings, rx_settings) = watch::channel(Arc::new(settings.clone()));
+            run_alerter_task(rx_alert, notifier.clone(), metrics.clone(), check_state.clone(), rx_settings, None, clock.clone());
+
+            let ctx = TimeoutContext::new(tx_alert, check_state, metrics, clock, None, Arc::new(EventLog::new(100)));
+            scheduler::run_ping_receiver_task(rx_ping, rx_commands, ctx, settings, notifier);
+
+            Self { tx_ping, tx_commands, tx_settings, mock }
+        }
+
+        /// Pings `id` and waits until the ping was processed.
+        async fn ping(&self, id: &str) {
+            let (reply, processed) = oneshot::channel();
+            let ping = Ping { id: id.to_string(), request_id: "test".to_string(), payload: None, reply: Some(reply) };
+
+            self.tx_ping.send(ping).await.unwrap();
+            processed.await.unwrap();
+        }
+
+        /// Sends the command built by `command` and waits until the check
+        /// it concerns was found.
+        async fn command(&self, command: impl FnOnce(oneshot::Sender<bool>) -> Command) {
+            let (reply, found) = oneshot::channel();
+            self.tx_commands.send(command(reply)).unwrap();
+            assert!(found.await.unwrap());
+        }
+
+        async fn acknowledge(&self, id: &str) {
+            self.command(|reply| Command::Acknowledge { id: id.to_string(), reply }).await;
+        }
+
+        async fn pause(&self, id: &str) {
+            self.command(|reply| Command::Pause { id: id.to_string(), reply }).await;
+        }
+
+        async fn resume(&self, id: &str) {
+            self.command(|reply| Command::Resume { id: id.to_string(), reply }).await;
+        }
+
+        async fn delete(&self, id: &str) {
+            self.command(|reply| Command::Delete { id: id.to_string(), reply }).await;
+        }
+
+        /// Forgets all checks, as `POST /admin/reset` does.
+        async fn reset(&self) {
+            let (reply, forgotten) = oneshot::channel();
+            self.tx_commands.send(Command::Reset { reply }).unwrap();
+            forgotten.await.unwrap();
+        }
+
+        /// Hands `yaml` to the ping receiver and the alerter, as the reload
+        /// task does.
+        fn reload(&self, yaml: &str) {
+            let settings = settings(yaml);
+            let notifier = CompositeNotifier::new(vec![("mock".to_string(), Box::new(self.mock.clone()))]);
+
+            self.tx_commands.send(Command::Reload { settings: Box::new(settings.clone()), notifier: Arc::new(notifier) }).unwrap();
+            self.tx_settings.send(Arc::new(settings)).unwrap();
+        }
+
+        /// Lets `seconds` pass; timers that expire meanwhile fire on time, as
+        /// tokio's paused clock skips ahead only when all tasks are idle.
+        async fn wait(&self, seconds: u64) {
+            tokio::time::sleep(Duration::from_secs(seconds)).await;
+        }
+
+        fn events(&self) -> Vec<(String, AlertEvent)> {
+            self.mock.events()
+        }
+    }
+
+    fn failure(id: &str) -> (String, AlertEvent) {
+        (id.to_string(), AlertEvent::Failure)
+    }
+
+    fn recovery(id: &str) -> (String, AlertEvent) {
+        (id.to_string(), AlertEvent::Recovery)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn alerts_after_interval_and_grace() {
+        let pipeline = Pipeline::start("checks: { a: { interval_seconds: 60, grace_seconds: 5 } }");
+
+        pipeline.ping("a").await;
+        pipeline.wait(64).await;
+        assert!(pipeline.events().is_empty());
+
+        pipeline.wait(2).await;
+        assert_eq!(pipeline.events(), vec![failure("a")]);
+
+        pipeline.ping("a").await;
+        pipeline.wait(1).await;
+        assert_eq!(pipeline.events(), vec![failure("a"), recovery("a")]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timely_pings_do_not_alert() {
+        let pipeline = Pipeline::start("checks: { a: { interval_seconds: 60 } }");
+
+        pipeline.ping("a").await;
+        pipeline.wait(50).await;
+        pipeline.ping("a").await;
+        pipeline.wait(50).await;
+
+        assert!(pipeline.events().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn escalation_is_suppressed_once_acknowledged() {
+        let yaml = "checks: { a: { interval_seconds: 60, escalate_after: 2, escalation_notifiers: [mock] } }";
+
+        let pipeline = Pipeline::start(yaml);
+        pipeline.ping("a").await;
+        pipeline.wait(130).await;
+        assert_eq!(pipeline.events(), vec![failure("a"), failure("a")]);
+
+        let pipeline = Pipeline::start(yaml);
+        pipeline.ping("a").await;
+        pipeline.wait(61).await;
+        pipeline.acknowledge("a").await;
+        pipeline.wait(69).await;
+        assert_eq!(pipeline.events(), vec![failure("a")]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failures_are_dropped_during_maintenance_windows() {
+        let pipeline = Pipeline::start(concat!(
+            "checks: { a: { interval_seconds: 60 } }\n",
+            "maintenance_windows: [{ cron: '0 0 10 * * Mon', duration_minutes: 60 }]\n",
+        ));
+
+        pipeline.ping("a").await;
+        pipeline.wait(61).await;
+        pipeline.ping("a").await;
+        pipeline.wait(1).await;
+
+        assert!(pipeline.events().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn repeated_failures_are_suppressed_during_cooldown() {
+        let check = "checks: { a: { interval_seconds: 60, repeat_until_ack: { interval_seconds: 60 } } }\n";
+
+        let pipeline = Pipeline::start(check);
+        pipeline.ping("a").await;
+        pipeline.wait(130).await;
+        assert_eq!(pipeline.events(), vec![failure("a"), failure("a")]);
+
+        let pipeline = Pipeline::start(&format!("{}alert_cooldown_seconds: 3600\n", check));
+        pipeline.ping("a").await;
+        pipeline.wait(130).await;
+        assert_eq!(pipeline.events(), vec![failure("a")]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failures_are_held_back_while_a_dependency_fails() {
+        let pipeline = Pipeline::start("checks: { a: { interval_seconds: 60, depends_on: [b] }, b: { interval_seconds: 30 } }");
+
+        pipeline.ping("a").await;
+        pipeline.ping("b").await;
+        pipeline.wait(61).await;
+        assert_eq!(pipeline.events(), vec![failure("b")]);
+
+        pipeline.ping("b").await;
+        pipeline.wait(1).await;
+        assert_eq!(pipeline.events(), vec![failure("b"), failure("a"), recovery("b")]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reloaded_maintenance_windows_apply_to_alerts() {
+        let check = "checks: { a: { interval_seconds: 60 } }\n";
+        let pipeline = Pipeline::start(check);
+
+        pipeline.reload(&format!("{}maintenance_windows: [{{ cron: '0 0 10 * * Mon', duration_minutes: 60 }}]\n", check));
+        pipeline.ping("a").await;
+        pipeline.wait(61).await;
+        assert!(pipeline.events().is_empty());
+
+        pipeline.reload(check);
+        pipeline.ping("a").await;
+        pipeline.wait(61).await;
+        assert_eq!(pipeline.events(), vec![failure("a")]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn group_alerts_again_after_reset() {
+        let pipeline = Pipeline::start("checks: { a: { interval_seconds: 60, group: g }, b: { interval_seconds: 60, group: g } }");
+        let group_failures = |pipeline: &Pipeline| pipeline.events().iter().filter(|e| **e == failure("g")).count();
+
+        pipeline.wait(61).await;
+        assert_eq!(group_failures(&pipeline), 1);
+
+        pipeline.reset().await;
+        pipeline.wait(61).await;
+        assert_eq!(group_failures(&pipeline), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resuming_a_check_paused_while_alerted_recovers_it() {
+        let pipeline = Pipeline::start("checks: { a: { interval_seconds: 60 } }");
+
+        pipeline.ping("a").await;
+        pipeline.wait(61).await;
+        pipeline.pause("a").await;
+        pipeline.wait(60).await;
+        assert_eq!(pipeline.events(), vec![failure("a")]);
+
+        pipeline.resume("a").await;
+        pipeline.wait(1).await;
+        assert_eq!(pipeline.events(), vec![failure("a"), recovery("a")]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resuming_a_healthy_check_does_not_recover_it() {
+        let pipeline = Pipeline::start("checks: { a: { interval_seconds: 60 } }");
+
+        pipeline.ping("a").await;
+        pipeline.pause("a").await;
+        pipeline.wait(120).await;
+        pipeline.resume("a").await;
+        pipeline.wait(1).await;
+
+        assert!(pipeline.events().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn deleting_an_alerted_check_recovers_it() {
+        let pipeline = Pipeline::start("");
+
+        pipeline.ping("a").await;
+        pipeline.wait(31).await;
+        pipeline.delete("a").await;
+        pipeline.wait(1).await;
+
+        assert_eq!(pipeline.events(), vec![failure("a"), recovery("a")]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn group_alerts_again_after_its_checks_were_paused_and_resumed() {
+        let pipeline = Pipeline::start("checks: { a: { interval_seconds: 60, group: g }, b: { interval_seconds: 60, group: g } }");
+        let group_events = |pipeline: &Pipeline| pipeline.events().into_iter().filter(|(id, _)| id == "g").collect::<Vec<_>>();
+
+        pipeline.wait(61).await;
+        assert_eq!(group_events(&pipeline), vec![failure("g")]);
+
+        pipeline.pause("a").await;
+        pipeline.pause("b").await;
+        pipeline.resume("a").await;
+        pipeline.resume("b").await;
+        pipeline.wait(1).await;
+        assert_eq!(group_events(&pipeline), vec![failure("g"), recovery("g")]);
+
+        pipeline.wait(60).await;
+        assert_eq!(group_events(&pipeline), vec![failure("g"), recovery("g"), failure("g")]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn group_recovers_when_its_last_failing_check_is_deleted() {
+        let pipeline = Pipeline::start("checks: { a: { interval_seconds: 60, group: g }, b: { interval_seconds: 60, group: g } }");
+
+        pipeline.wait(61).await;
+        pipeline.ping("a").await;
+        pipeline.delete("b").await;
+        pipeline.wait(1).await;
+
+        let group_events = pipeline.events().into_iter().filter(|(id, _)| id == "g").collect::<Vec<_>>();
+        assert_eq!(group_events, vec![failure("g"), recovery("g")]);
+    }
+
+    #[tokio::test]
+    async fn single_and_bulk_pings_check_the_rate_limit_before_max_checks() {
+        let check_state = state::new_shared_state();
+        let known: CheckState = serde_json::from_value(json!({ "status": "healthy", "last_ping": null })).unwrap();
+        check_state.lock().unwrap().insert("a".to_string(), known);
+
+        let health = Arc::new(Health::default());
+        health.mark_ready();
+
+        let settings = settings("server: { max_checks: 1, ping_rate_limit: 1 }");
+        let policy = filters::ping_policy(&settings, check_state, health);
+        let (tx_ping, _rx_ping) = mpsc::channel(10);
+        let routes = filters::ping_routes(tx_ping, Arc::new(Metrics::default()), None, &settings, policy);
+
+        let single = || warp::test::request().method("POST").path("/ping/b").reply(&routes);
+        assert_eq!(single().await.status(), StatusCode::INSUFFICIENT_STORAGE);
+        assert_eq!(single().await.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        let bulk = || warp::test::request().method("POST").path("/ping").json(&json!({ "ids": ["c"] })).reply(&routes);
+        assert_eq!(bulk().await.status(), StatusCode::INSUFFICIENT_STORAGE);
+        assert_eq!(bulk().await.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    /// A dispatcher that sends one notification at a time to a mock notifier.
+    fn dispatcher() -> (Dispatcher, MockNotifier) {
+        let mock = MockNotifier::default();
+        let notifier: SharedNotifier = Arc::new(RwLock::new(Arc::new(CompositeNotifier::new(vec![("mock".to_string(), Box::new(mock.clone()))]))));
+
+        (Dispatcher::new(notifier, Arc::new(Metrics::default()), 1), mock)
+    }
+
+    fn alert(id: &str, severity: Severity) -> Alert {
+        Alert {
+            id: id.to_string(),
+            event: AlertEvent::Failure,
+            targets: None,
+            payload: None,
+            last_ping: None,
+            labels: HashMap::new(),
+            severity,
+            consecutive_misses: 1,
+            message: None,
+            timestamp: "2026-01-05T10:00:00Z".parse().unwrap(),
+        }
+    }
+
+    /// Waits for all running and waiting notifications to be sent.
+    async fn drain(dispatcher: &mut Dispatcher) {
+        while dispatcher.join_next().await.is_some() {}
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn critical_notifications_are_sent_before_informational_ones() {
+        let (mut dispatcher, mock) = dispatcher();
+
+        // The first notification takes the only slot right away.
+        dispatcher.dispatch(None, alert("running", Severity::Info));
+        dispatcher.dispatch(None, alert("info", Severity::Info));
+        dispatcher.dispatch(None, alert("warning", Severity::Warning));
+        dispatcher.dispatch(None, alert("critical", Severity::Critical));
+        drain(&mut dispatcher).await;
+
+        let ids: Vec<String> = mock.events().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec!["running", "critical", "warning", "info"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn informational_notifications_wait_at_most_max_priority_delay() {
+        let (mut dispatcher, mock) = dispatcher();
+
+        dispatcher.dispatch(None, alert("running", Severity::Info));
+        dispatcher.dispatch(None, alert("info", Severity::Info));
+        tokio::time::advance(MAX_PRIORITY_DELAY).await;
+        dispatcher.dispatch(None, alert("critical", Severity::Critical));
+        drain(&mut dispatcher).await;
+
+        let ids: Vec<String> = mock.events().into_iter().map(|(id, _)| id).collect();
+        assert_eq!(ids, vec!["running", "info", "critical"]);
+    }
+}
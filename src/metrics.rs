@@ -0,0 +1,66 @@
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+/// Process-wide counters and gauges, exposed in the Prometheus text format.
+#[derive(Default, Debug)]
+pub struct Metrics {
+    pings_received: AtomicU64,
+    pings_dropped: AtomicU64,
+    alerts_fired: AtomicU64,
+    notifier_errors: AtomicU64,
+    checks_overdue: AtomicI64,
+}
+
+impl Metrics {
+    pub fn inc_pings_received(&self) {
+        self.pings_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_pings_dropped(&self) {
+        self.pings_dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_alerts_fired(&self) {
+        self.alerts_fired.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_notifier_errors(&self) {
+        self.notifier_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_checks_overdue(&self) {
+        self.checks_overdue.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn dec_checks_overdue(&self) {
+        self.checks_overdue.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        write_metric(&mut out, "dodemansknop_pings_received_total", "counter",
+                     "Total number of pings received.",
+                     self.pings_received.load(Ordering::Relaxed));
+        write_metric(&mut out, "dodemansknop_pings_dropped_total", "counter",
+                     "Total number of pings rejected because the ping queue was full.",
+                     self.pings_dropped.load(Ordering::Relaxed));
+        write_metric(&mut out, "dodemansknop_alerts_fired_total", "counter",
+                     "Total number of alerts fired for missed pings.",
+                     self.alerts_fired.load(Ordering::Relaxed));
+        write_metric(&mut out, "dodemansknop_notifier_errors_total", "counter",
+                     "Total number of errors returned by notifiers.",
+                     self.notifier_errors.load(Ordering::Relaxed));
+        write_metric(&mut out, "dodemansknop_checks_overdue", "gauge",
+                     "Number of checks that are currently overdue.",
+                     self.checks_overdue.load(Ordering::Relaxed));
+
+        out
+    }
+}
+
+fn write_metric<T: std::fmt::Display>(out: &mut String, name: &str, kind: &str, help: &str, value: T) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    let _ = writeln!(out, "{} {}", name, value);
+}
@@ -1,20 +1,756 @@
-use log::info;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
 
-#[derive(Clone, Debug)]
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::{join_all, BoxFuture};
+use log::{info, warn};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::config::Severity;
+use crate::error::DodemansknopError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertEvent {
+    /// The check missed its ping.
+    Failure,
+
+    /// The check pinged again after a failure had been notified.
+    Recovery,
+}
+
+impl AlertEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertEvent::Failure => "failure",
+            AlertEvent::Recovery => "recovery",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Alert {
     pub id: String,
+    pub event: AlertEvent,
+
+    /// Names of the notifiers this alert should be sent to; `None` means all.
+    pub targets: Option<Vec<String>>,
+
+    /// Body of the check's last ping, e.g. the last status reported by the
+    /// job. Only set for failures.
+    pub payload: Option<String>,
+
+    /// Time of the check's last ping; `None` if it never pinged, or for
+    /// recoveries. For groups, this is the last ping of the check whose
+    /// failure took the whole group down.
+    #[serde(default)]
+    pub last_ping: Option<DateTime<Utc>>,
+
+    /// Labels of the check, as configured.
+    pub labels: HashMap<String, String>,
+
+    pub severity: Severity,
+
+    /// Number of intervals the check missed in a row; for recoveries, the
+    /// number it had missed before it pinged again.
+    #[serde(default)]
+    pub consecutive_misses: u32,
+
+    /// Text rendered from the check's `alert_message` or `recovery_message`
+    /// template, which chat notifiers send instead of their default text.
+    #[serde(default)]
+    pub message: Option<String>,
+
+    /// Time at which the alert was raised, as read from the scheduler's
+    /// clock; notifiers report this rather than the time of sending, which
+    /// differs for batched and retried alerts.
+    #[serde(default)]
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Alert {
+    /// Whole seconds between the check's last ping and the alert being
+    /// raised, if it ever pinged.
+    pub fn seconds_since_last_ping(&self) -> Option<i64> {
+        self.last_ping.map(|t| (self.timestamp - t).num_seconds().max(0))
+    }
+
+    /// Describes when the check last pinged, e.g. "last pinged 47 seconds
+    /// ago" or "never pinged".
+    pub fn last_ping_description(&self) -> String {
+        match self.seconds_since_last_ping() {
+            Some(seconds) => format!("last pinged {} ago", describe_duration(seconds)),
+            None => "never pinged".to_string(),
+        }
+    }
+}
+
+/// Version of [`AlertPayload`]; raised whenever fields are renamed or
+/// removed, but not when fields are added.
+pub const ALERT_SCHEMA_VERSION: u32 = 1;
+
+/// The facts about an alert that notifiers sending structured data pass on,
+/// so that they all present the same information. Field names are
+/// snake_case and only change along with `schema_version`.
+#[derive(Clone, Debug, Serialize)]
+pub struct AlertPayload<'a> {
+    pub schema_version: u32,
+    pub id: &'a str,
+    pub event: AlertEvent,
+
+    /// The same as `id` and `event` (as "failed" or "recovered"), under the
+    /// names that many receivers expect.
+    pub check_id: &'a str,
+    pub status: &'static str,
+
+    pub severity: Severity,
+    pub message: String,
+    pub consecutive_misses: u32,
+
+    /// `None` if the check never pinged, and for recoveries.
+    pub last_ping: Option<String>,
+    pub seconds_since_last_ping: Option<i64>,
+    pub labels: &'a HashMap<String, String>,
+
+    /// Body of the check's last ping, only present if there is one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<&'a str>,
+}
+
+impl<'a> AlertPayload<'a> {
+    /// Describes `alert` with `message` as human-readable text.
+    pub fn new(alert: &'a Alert, message: String) -> Self {
+        Self {
+            schema_version: ALERT_SCHEMA_VERSION,
+            id: &alert.id,
+            event: alert.event,
+            check_id: &alert.id,
+            status: match alert.event {
+                AlertEvent::Failure => "failed",
+                AlertEvent::Recovery => "recovered",
+            },
+            severity: alert.severity,
+            message,
+            consecutive_misses: alert.consecutive_misses,
+            last_ping: alert.last_ping.map(|t| t.to_rfc3339()),
+            seconds_since_last_ping: alert.seconds_since_last_ping(),
+            labels: &alert.labels,
+            payload: alert.payload.as_deref(),
+        }
+    }
+}
+
+/// Failures of several checks that are notified together, when batching is
+/// enabled; versioned along with [`AlertPayload`].
+#[derive(Clone, Debug, Serialize)]
+pub struct BatchPayload<'a> {
+    pub schema_version: u32,
+    pub event: AlertEvent,
+    pub status: &'static str,
+
+    /// The highest severity of the batched alerts.
+    pub severity: Severity,
+    pub message: String,
+    pub ids: Vec<&'a str>,
+
+    /// Payloads of the batched alerts, in the order they were raised.
+    pub alerts: Vec<AlertPayload<'a>>,
+}
+
+impl<'a> BatchPayload<'a> {
+    /// Describes the failures in `alerts` with `message` as human-readable
+    /// text, and each of them with the text returned by `describe`.
+    pub fn new(alerts: &'a [Alert], message: String, describe: impl Fn(&Alert) -> String) -> Self {
+        Self {
+            schema_version: ALERT_SCHEMA_VERSION,
+            event: AlertEvent::Failure,
+            status: "failed",
+            severity: alerts.iter().map(|a| a.severity).max().unwrap_or_default(),
+            message,
+            ids: alerts.iter().map(|a| a.id.as_str()).collect(),
+            alerts: alerts.iter().map(|a| AlertPayload::new(a, describe(a))).collect(),
+        }
+    }
+}
+
+/// The facts about a heartbeat that notifiers sending structured data pass
+/// on; versioned along with [`AlertPayload`].
+#[derive(Clone, Debug, Serialize)]
+pub struct HeartbeatPayload {
+    pub schema_version: u32,
+
+    /// Always "heartbeat", to tell heartbeats apart from alerts.
+    pub event: &'static str,
+    pub message: String,
+    pub healthy: usize,
+    pub overdue: usize,
+    pub paused: usize,
+    pub timestamp: String,
+}
+
+impl HeartbeatPayload {
+    pub fn new(heartbeat: &Heartbeat) -> Self {
+        Self {
+            schema_version: ALERT_SCHEMA_VERSION,
+            event: "heartbeat",
+            message: heartbeat.message(),
+            healthy: heartbeat.healthy,
+            overdue: heartbeat.overdue,
+            paused: heartbeat.paused,
+            timestamp: heartbeat.timestamp.to_rfc3339(),
+        }
+    }
+}
+
+/// Describes a duration in the largest unit that keeps it precise enough,
+/// e.g. "47 seconds" or "3 hours".
+pub fn describe_duration(seconds: i64) -> String {
+    let (value, unit) = match seconds {
+        s if s < 120 => (s, "second"),
+        s if s < 120 * 60 => (s / 60, "minute"),
+        s if s < 48 * 3600 => (s / 3600, "hour"),
+        s => (s / 86400, "day"),
+    };
+
+    let plural = if value == 1 { "" } else { "s" };
+    format!("{} {}{}", value, unit, plural)
+}
+
+/// Summary of the state of all checks, sent periodically to show that
+/// dodemansknop itself is running.
+#[derive(Clone, Debug)]
+pub struct Heartbeat {
+    /// Names of the notifiers this heartbeat should be sent to; `None` means
+    /// all.
+    pub targets: Option<Vec<String>>,
+
+    pub healthy: usize,
+    pub overdue: usize,
+    pub paused: usize,
+
+    /// Time at which the heartbeat was taken.
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Heartbeat {
+    pub fn message(&self) -> String {
+        format!("dodemansknop is running: {} checks healthy, {} overdue, {} paused", self.healthy, self.overdue, self.paused)
+    }
 }
 
-pub trait Notifier: Send {
-    fn notify_failure(&self, alert: Alert) -> Result<(), &'static str>;
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError>;
+
+    /// Called when a check that previously triggered a failure notification
+    /// pings again. Notifiers that have no notion of recovery may ignore it.
+    async fn notify_recovery(&self, _alert: Alert) -> Result<(), DodemansknopError> {
+        Ok(())
+    }
+
+    /// Checks that the notifier can reach its destination, without sending
+    /// a notification, to detect misconfiguration early.
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        Ok(())
+    }
+
+    /// Called with failures of several checks that occurred at about the
+    /// same time, when batching is enabled. Notifiers that cannot combine
+    /// them send one notification per failure; all are attempted, and the
+    /// first error is returned.
+    async fn notify_failures(&self, alerts: &[Alert]) -> Result<(), DodemansknopError> {
+        let mut result = Ok(());
+
+        for alert in alerts {
+            if let Err(e) = self.notify_failure(alert.clone()).await {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Called periodically when heartbeats are enabled. Notifiers that are
+    /// meant for actual incidents (such as PagerDuty) ignore it.
+    async fn notify_heartbeat(&self, _heartbeat: &Heartbeat) -> Result<(), DodemansknopError> {
+        Ok(())
+    }
 }
 
 #[derive(Copy, Clone)]
 pub struct NoOpNotifier {}
 
+#[async_trait]
 impl Notifier for NoOpNotifier {
-    fn notify_failure(&self, alert: Alert) -> Result<(), &'static str> {
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
         info!("missed alert for {}: {:?}", alert.id, alert);
         Ok(())
     }
+
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        info!("recovered alert for {}: {:?}", alert.id, alert);
+        Ok(())
+    }
+
+    async fn notify_heartbeat(&self, heartbeat: &Heartbeat) -> Result<(), DodemansknopError> {
+        info!("heartbeat: {}", heartbeat.message());
+        Ok(())
+    }
+}
+
+/// Notifier for tests that records the alerts it received, in order. While
+/// `failing` is set, it fails instead, recording nothing.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct MockNotifier {
+    pub alerts: Arc<Mutex<Vec<Alert>>>,
+    pub failing: Arc<std::sync::atomic::AtomicBool>,
+}
+
+#[cfg(test)]
+impl MockNotifier {
+    /// IDs and events of the recorded alerts.
+    pub fn events(&self) -> Vec<(String, AlertEvent)> {
+        self.alerts.lock().unwrap().iter().map(|a| (a.id.clone(), a.event)).collect()
+    }
+
+    pub fn set_failing(&self, failing: bool) {
+        self.failing.store(failing, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    fn record(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        if self.failing.load(std::sync::atomic::Ordering::SeqCst) {
+            return Err(DodemansknopError::Transport("mock notifier failed".to_string()));
+        }
+
+        self.alerts.lock().unwrap().push(alert);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl Notifier for MockNotifier {
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.record(alert)
+    }
+
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.record(alert)
+    }
+}
+
+/// Time after which a reachability probe is considered failed.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Checks that `url` is reachable by sending a HEAD request. Any response
+/// counts as success, as receivers are not expected to support HEAD; only
+/// connection errors (e.g. unknown hosts) fail. `target` names the notifier
+/// in the error, which does not include the URL since it may contain
+/// credentials.
+pub async fn probe(client: &Client, target: &str, url: &str) -> Result<(), DodemansknopError> {
+    client.head(url)
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+        .map(|_| ())
+        .map_err(|e| DodemansknopError::Transport(format!("{} endpoint is not reachable: {}", target, e.without_url())))
+}
+
+/// Notifier set that is used by the alerter, and may be replaced when the
+/// configuration is reloaded.
+pub type SharedNotifier = Arc<RwLock<Arc<CompositeNotifier>>>;
+
+/// Notifier that forwards each alert to a set of named notifiers. Alerts
+/// with `targets` are only forwarded to the notifiers with matching names.
+///
+/// The notifiers are called concurrently. A failing notifier does not
+/// prevent the remaining notifiers from being attempted; all errors are
+/// collected and returned together.
+pub struct CompositeNotifier {
+    notifiers: Vec<(String, Box<dyn Notifier>)>,
+}
+
+impl CompositeNotifier {
+    pub fn new(notifiers: Vec<(String, Box<dyn Notifier>)>) -> Self {
+        Self { notifiers }
+    }
+
+    /// Calls all notifiers named in `targets` (or all, if `None`)
+    /// concurrently, using `f` to pick the notification method.
+    async fn each<'a, F>(&'a self, targets: &Option<Vec<String>>, f: F) -> Result<(), DodemansknopError>
+        where F: Fn(&'a dyn Notifier) -> BoxFuture<'a, Result<(), DodemansknopError>>
+    {
+        let targeted: Vec<&(String, Box<dyn Notifier>)> = self.notifiers.iter()
+            .filter(|(name, _)| match targets {
+                Some(ref targets) => targets.contains(name),
+                None => true,
+            })
+            .collect();
+
+        let results = join_all(targeted.iter().map(|(_, n)| f(n.as_ref()))).await;
+
+        collect_errors(targeted.iter().map(|(name, _)| name).zip(results))
+    }
+}
+
+/// Combines the results of several named notifiers into one.
+fn collect_errors<'a>(results: impl Iterator<Item = (&'a String, Result<(), DodemansknopError>)>) -> Result<(), DodemansknopError> {
+    let errors: Vec<(String, DodemansknopError)> = results
+        .filter_map(|(name, result)| result.err().map(|e| (name.clone(), e)))
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(DodemansknopError::Notifiers(errors))
+    }
+}
+
+#[async_trait]
+impl Notifier for CompositeNotifier {
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.each(&alert.targets, |n| n.notify_failure(alert.clone())).await
+    }
+
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.each(&alert.targets, |n| n.notify_recovery(alert.clone())).await
+    }
+
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        let results = join_all(self.notifiers.iter().map(|(_, n)| n.verify())).await;
+
+        collect_errors(self.notifiers.iter().map(|(name, _)| name).zip(results))
+    }
+
+    /// Sends each notifier one batch of the failures targeted at it.
+    async fn notify_failures(&self, alerts: &[Alert]) -> Result<(), DodemansknopError> {
+        let batches: Vec<(&String, &dyn Notifier, Vec<Alert>)> = self.notifiers.iter()
+            .filter_map(|(name, n)| {
+                let batch: Vec<Alert> = alerts.iter()
+                    .filter(|a| match a.targets {
+                        Some(ref targets) => targets.contains(name),
+                        None => true,
+                    })
+                    .cloned()
+                    .collect();
+
+                if batch.is_empty() {
+                    None
+                } else {
+                    Some((name, n.as_ref(), batch))
+                }
+            })
+            .collect();
+
+        let results = join_all(batches.iter().map(|(_, n, batch)| n.notify_failures(batch))).await;
+
+        collect_errors(batches.iter().map(|(name, _, _)| *name).zip(results))
+    }
+
+    async fn notify_heartbeat(&self, heartbeat: &Heartbeat) -> Result<(), DodemansknopError> {
+        self.each(&heartbeat.targets, |n| n.notify_heartbeat(heartbeat)).await
+    }
+}
+
+/// Notifier that cuts off calls of another notifier that take longer than
+/// `timeout`, so that no notifier can hold up alert dispatch indefinitely.
+/// A call that is cut off fails with a temporary error.
+pub struct TimeoutNotifier {
+    name: String,
+    inner: Box<dyn Notifier>,
+    timeout: Duration,
+}
+
+impl TimeoutNotifier {
+    pub fn new(name: String, inner: Box<dyn Notifier>, timeout: Duration) -> Self {
+        Self { name, inner, timeout }
+    }
+
+    async fn limit<'a>(&self, call: &str, f: BoxFuture<'a, Result<(), DodemansknopError>>) -> Result<(), DodemansknopError> {
+        match tokio::time::timeout(self.timeout, f).await {
+            Ok(result) => result,
+            Err(_) => {
+                warn!(notifier = self.name; "notifier {} did not complete {} within {:?}; cutting it off", self.name, call, self.timeout);
+                Err(DodemansknopError::Transport(format!("{} timed out after {:?}", call, self.timeout)))
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for TimeoutNotifier {
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.limit("failure notification", self.inner.notify_failure(alert)).await
+    }
+
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.limit("recovery notification", self.inner.notify_recovery(alert)).await
+    }
+
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        self.limit("verification", self.inner.verify()).await
+    }
+
+    async fn notify_failures(&self, alerts: &[Alert]) -> Result<(), DodemansknopError> {
+        self.limit("batched failure notification", self.inner.notify_failures(alerts)).await
+    }
+
+    async fn notify_heartbeat(&self, heartbeat: &Heartbeat) -> Result<(), DodemansknopError> {
+        self.limit("heartbeat", self.inner.notify_heartbeat(heartbeat)).await
+    }
+}
+
+/// Notifier that stops calling a repeatedly failing notifier for a while,
+/// so that alerts fail fast instead of piling up behind retries and
+/// timeouts.
+///
+/// After `failure_threshold` consecutive failures the circuit opens, and
+/// all notifications fail immediately for `cooldown`. The next notification
+/// after that is passed through as a trial (half-open): when it succeeds the
+/// circuit closes, when it fails the circuit opens again.
+pub struct CircuitBreakerNotifier {
+    name: String,
+    inner: Box<dyn Notifier>,
+    failure_threshold: u32,
+    cooldown: Duration,
+
+    state: Mutex<CircuitState>,
+}
+
+#[derive(Default)]
+struct CircuitState {
+    consecutive_failures: u32,
+
+    /// When the circuit was opened; `None` while it is closed.
+    opened_at: Option<Instant>,
+
+    /// Whether a trial notification is in flight.
+    half_open: bool,
+}
+
+impl CircuitBreakerNotifier {
+    pub fn new(name: String, inner: Box<dyn Notifier>, failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            name,
+            inner,
+            failure_threshold,
+            cooldown,
+            state: Mutex::new(CircuitState::default()),
+        }
+    }
+
+    /// Decides whether a notification may be passed to the inner notifier.
+    fn admit(&self) -> Result<(), DodemansknopError> {
+        let mut state = self.state.lock().unwrap();
+
+        match state.opened_at {
+            None => Ok(()),
+            Some(opened_at) if opened_at.elapsed() >= self.cooldown && !state.half_open => {
+                info!(notifier = self.name; "circuit of notifier {} is half-open; trying a notification", self.name);
+                state.half_open = true;
+                Ok(())
+            }
+            Some(_) => Err(DodemansknopError::Transport(format!("circuit of notifier {} is open; notification skipped", self.name))),
+        }
+    }
+
+    fn record(&self, result: &Result<(), DodemansknopError>) {
+        let mut state = self.state.lock().unwrap();
+
+        if result.is_ok() {
+            if state.opened_at.is_some() {
+                info!(notifier = self.name; "circuit of notifier {} closed", self.name);
+            }
+
+            *state = CircuitState::default();
+            return;
+        }
+
+        state.consecutive_failures += 1;
+
+        if state.half_open {
+            warn!(notifier = self.name; "trial notification of notifier {} failed; circuit opened again for {:?}", self.name, self.cooldown);
+            state.half_open = false;
+            state.opened_at = Some(Instant::now());
+        } else if state.opened_at.is_none() && state.consecutive_failures >= self.failure_threshold {
+            warn!(notifier = self.name; "notifier {} failed {} times in a row; circuit opened for {:?}", self.name, state.consecutive_failures, self.cooldown);
+            state.opened_at = Some(Instant::now());
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for CircuitBreakerNotifier {
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.admit()?;
+        let result = self.inner.notify_failure(alert).await;
+        self.record(&result);
+        result
+    }
+
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.admit()?;
+        let result = self.inner.notify_recovery(alert).await;
+        self.record(&result);
+        result
+    }
+
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        self.inner.verify().await
+    }
+
+    async fn notify_failures(&self, alerts: &[Alert]) -> Result<(), DodemansknopError> {
+        self.admit()?;
+        let result = self.inner.notify_failures(alerts).await;
+        self.record(&result);
+        result
+    }
+
+    async fn notify_heartbeat(&self, heartbeat: &Heartbeat) -> Result<(), DodemansknopError> {
+        self.admit()?;
+        let result = self.inner.notify_heartbeat(heartbeat).await;
+        self.record(&result);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn alert(id: &str, event: AlertEvent) -> Alert {
+        Alert {
+            id: id.to_string(),
+            event,
+            targets: None,
+            payload: None,
+            last_ping: None,
+            labels: HashMap::new(),
+            severity: Severity::Critical,
+            consecutive_misses: 1,
+            message: None,
+            timestamp: "2026-01-05T10:00:00Z".parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn last_ping_is_described_as_of_the_time_the_alert_was_raised() {
+        let mut failure = alert("backup", AlertEvent::Failure);
+        failure.last_ping = Some(failure.timestamp - chrono::Duration::seconds(47));
+
+        assert_eq!(failure.seconds_since_last_ping(), Some(47));
+        assert_eq!(failure.last_ping_description(), "last pinged 47 seconds ago");
+    }
+
+    #[test]
+    fn payload_names_check_id_and_status() {
+        let failure = alert("backup", AlertEvent::Failure);
+        let payload = json!(AlertPayload::new(&failure, "missed".to_string()));
+
+        assert_eq!(payload["check_id"], "backup");
+        assert_eq!(payload["status"], "failed");
+        assert_eq!(payload["id"], "backup");
+        assert_eq!(payload["event"], "failure");
+
+        let recovery = alert("backup", AlertEvent::Recovery);
+        assert_eq!(json!(AlertPayload::new(&recovery, "recovered".to_string()))["status"], "recovered");
+    }
+
+    #[test]
+    fn payload_escapes_check_ids() {
+        let failure = alert("a\"b\\c", AlertEvent::Failure);
+        let body = json!(AlertPayload::new(&failure, String::new())).to_string();
+
+        let parsed: serde_json::Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(parsed["check_id"], "a\"b\\c");
+    }
+
+    #[test]
+    fn batch_payload_carries_a_payload_per_alert() {
+        let mut backup = alert("backup", AlertEvent::Failure);
+        backup.severity = Severity::Info;
+        let alerts = [backup, alert("cleanup", AlertEvent::Failure)];
+
+        let body = json!(BatchPayload::new(&alerts, "2 failed".to_string(), |a| format!("{} failed", a.id)));
+
+        assert_eq!(body["schema_version"], ALERT_SCHEMA_VERSION);
+        assert_eq!(body["status"], "failed");
+        assert_eq!(body["severity"], "critical");
+        assert_eq!(body["ids"], json!(["backup", "cleanup"]));
+        assert_eq!(body["alerts"][0]["check_id"], "backup");
+        assert_eq!(body["alerts"][0]["severity"], "info");
+        assert_eq!(body["alerts"][1]["message"], "cleanup failed");
+    }
+
+    /// A circuit breaker around a failing mock notifier that opens after two
+    /// failures, for a minute.
+    fn breaker() -> (CircuitBreakerNotifier, MockNotifier) {
+        let mock = MockNotifier::default();
+        mock.set_failing(true);
+
+        (CircuitBreakerNotifier::new("mock".to_string(), Box::new(mock.clone()), 2, Duration::from_secs(60)), mock)
+    }
+
+    /// Whether `result` is the error of an open circuit, rather than one of
+    /// the notifier behind it.
+    fn skipped(result: Result<(), DodemansknopError>) -> bool {
+        result.is_err_and(|e| e.to_string().contains("circuit of notifier mock is open"))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn circuit_opens_after_the_failure_threshold() {
+        let (breaker, mock) = breaker();
+
+        assert!(!skipped(breaker.notify_failure(alert("a", AlertEvent::Failure)).await));
+        assert!(!skipped(breaker.notify_failure(alert("a", AlertEvent::Failure)).await));
+        assert!(skipped(breaker.notify_failure(alert("a", AlertEvent::Failure)).await));
+
+        // The notifier is not tried again before the cooldown has passed.
+        mock.set_failing(false);
+        tokio::time::advance(Duration::from_secs(59)).await;
+        assert!(skipped(breaker.notify_recovery(alert("a", AlertEvent::Recovery)).await));
+        assert!(mock.events().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn successful_trial_closes_the_circuit() {
+        let (breaker, mock) = breaker();
+        for _ in 0..2 {
+            let _ = breaker.notify_failure(alert("a", AlertEvent::Failure)).await;
+        }
+
+        mock.set_failing(false);
+        tokio::time::advance(Duration::from_secs(60)).await;
+
+        assert!(breaker.notify_failure(alert("b", AlertEvent::Failure)).await.is_ok());
+        assert!(breaker.notify_recovery(alert("b", AlertEvent::Recovery)).await.is_ok());
+        assert_eq!(mock.events(), vec![("b".to_string(), AlertEvent::Failure), ("b".to_string(), AlertEvent::Recovery)]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_trial_opens_the_circuit_again() {
+        let (breaker, _mock) = breaker();
+        for _ in 0..2 {
+            let _ = breaker.notify_failure(alert("a", AlertEvent::Failure)).await;
+        }
+
+        tokio::time::advance(Duration::from_secs(60)).await;
+        assert!(!skipped(breaker.notify_failure(alert("a", AlertEvent::Failure)).await));
+        assert!(skipped(breaker.notify_failure(alert("a", AlertEvent::Failure)).await));
+
+        // The cooldown starts over with the failed trial.
+        tokio::time::advance(Duration::from_secs(59)).await;
+        assert!(skipped(breaker.notify_failure(alert("a", AlertEvent::Failure)).await));
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(!skipped(breaker.notify_failure(alert("a", AlertEvent::Failure)).await));
+    }
 }
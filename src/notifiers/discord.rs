@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use log::debug;
+use reqwest::{Client, Response};
+use reqwest::{Method, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::error::DodemansknopError;
+use crate::notifier::{self, Alert, Notifier};
+
+/// Maximum length of a Discord message's content, in characters.
+const MAX_CONTENT_LENGTH: usize = 2000;
+
+/// Upper bound for the time we are willing to wait when rate limited.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+pub struct DiscordNotifier {
+    url: String,
+    username: Option<String>,
+    avatar_url: Option<String>,
+
+    client: Client,
+}
+
+#[derive(Deserialize)]
+struct RateLimitResponse {
+    retry_after: f64,
+}
+
+impl DiscordNotifier {
+    pub fn new(url: String, username: Option<String>, avatar_url: Option<String>) -> Self {
+        Self {
+            url,
+            username,
+            avatar_url,
+            client: Client::new(),
+        }
+    }
+
+    async fn execute(&self, body: &Value) -> Result<Response, DodemansknopError> {
+        let req = self.client
+            .request(Method::POST, &self.url)
+            .json(body)
+            .build()
+            .map_err(|e| DodemansknopError::Config(format!("could not build discord request: {}", e)))?;
+
+        debug!("executing request: {:?}", req);
+
+        let res = self.client.execute(req).await
+            .map_err(|e| DodemansknopError::Transport(format!("could not send discord request: {}", e)))?;
+        debug!("response: {:?}", res);
+
+        Ok(res)
+    }
+
+    /// Posts a message, retrying once if rate limited.
+    async fn send(&self, content: String) -> Result<(), DodemansknopError> {
+        let content = truncate(content);
+
+        let mut body = json!({ "content": content });
+
+        if let Some(ref username) = self.username {
+            body["username"] = json!(username);
+        }
+
+        if let Some(ref avatar_url) = self.avatar_url {
+            body["avatar_url"] = json!(avatar_url);
+        }
+
+        let mut res = self.execute(&body).await?;
+
+        // When rate limited, Discord tells us how long to wait (in seconds);
+        // back off and try exactly once more.
+        if res.status() == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = res.json::<RateLimitResponse>().await
+                .map(|r| Duration::from_secs_f64(r.retry_after.max(0.0)))
+                .unwrap_or(Duration::from_secs(1))
+                .min(MAX_RETRY_AFTER);
+
+            debug!("rate limited by discord; retrying after {:?}", retry_after);
+            tokio::time::sleep(retry_after).await;
+
+            res = self.execute(&body).await?;
+        }
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_default();
+            return Err(DodemansknopError::from_status("discord", status, &text));
+        }
+
+        Ok(())
+    }
+}
+
+fn truncate(content: String) -> String {
+    if content.chars().count() <= MAX_CONTENT_LENGTH {
+        return content;
+    }
+
+    let mut truncated: String = content.chars().take(MAX_CONTENT_LENGTH - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        notifier::probe(&self.client, "discord", &self.url).await
+    }
+
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let mut content = match alert.message {
+            Some(ref message) => format!(":rotating_light: {}", message),
+            None => format!(":rotating_light: Service **{}** missed its dead mans switch; it {}", alert.id, alert.last_ping_description()),
+        };
+
+        if let Some(ref payload) = alert.payload {
+            content.push_str(&format!("\nLast payload:\n```{}```", payload));
+        }
+
+        self.send(content).await
+    }
+
+    /// Recoveries are only reported for checks with a recovery message.
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        match alert.message {
+            Some(message) => self.send(format!(":white_check_mark: {}", message)).await,
+            None => Ok(()),
+        }
+    }
+}
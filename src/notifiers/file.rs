@@ -0,0 +1,95 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::SecondsFormat;
+
+use crate::clock::Clock;
+use crate::error::DodemansknopError;
+use crate::notifier::{Alert, Heartbeat, Notifier};
+
+/// Notifier that appends a line per alert to a local file, as an audit trail
+/// that does not depend on any external service.
+pub struct FileNotifier {
+    path: String,
+    file: Mutex<File>,
+    clock: Arc<dyn Clock>,
+}
+
+impl FileNotifier {
+    /// Opens the file at `path`, creating it if necessary. Unless `append`
+    /// is set, an existing file is truncated.
+    pub fn new(path: String, append: bool, clock: Arc<dyn Clock>) -> Result<Self, DodemansknopError> {
+        let dir = match Path::new(&path).parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+
+        if !dir.is_dir() {
+            return Err(DodemansknopError::Config(format!("directory of alert log file '{}' does not exist", path)));
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(&path)
+            .map_err(|e| DodemansknopError::Config(format!("could not open alert log file '{}': {}", path, e)))?;
+
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+            clock,
+        })
+    }
+
+    fn write_alert(&self, alert: &Alert) -> Result<(), DodemansknopError> {
+        let mut line = format!(
+            "{} {} {} severity={}",
+            self.clock.now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            alert.event.as_str(),
+            alert.id,
+            alert.severity.as_str(),
+        );
+
+        // Quoted, so that multi-line payloads stay on a single line.
+        if let Some(ref payload) = alert.payload {
+            line.push_str(&format!(" payload={:?}", payload));
+        }
+
+        self.write(line)
+    }
+
+    fn write(&self, mut line: String) -> Result<(), DodemansknopError> {
+        line.push('\n');
+
+        let mut file = self.file.lock().unwrap();
+        file.write_all(line.as_bytes())
+            .and_then(|_| file.flush())
+            .map_err(|e| DodemansknopError::Transport(format!("could not write to alert log file '{}': {}", self.path, e)))
+    }
+}
+
+#[async_trait]
+impl Notifier for FileNotifier {
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.write_alert(&alert)
+    }
+
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.write_alert(&alert)
+    }
+
+    async fn notify_heartbeat(&self, heartbeat: &Heartbeat) -> Result<(), DodemansknopError> {
+        self.write(format!(
+            "{} heartbeat healthy={} overdue={} paused={}",
+            self.clock.now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            heartbeat.healthy,
+            heartbeat.overdue,
+            heartbeat.paused,
+        ))
+    }
+}
@@ -0,0 +1,135 @@
+use async_trait::async_trait;
+use log::debug;
+use reqwest::{Client, Method, Response, Url};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::config::GotifySettings;
+use crate::error::DodemansknopError;
+use crate::notifier::{Alert, Notifier};
+
+/// Notifier that pushes a message to a Gotify server, which forwards it to
+/// the clients subscribed to the application.
+#[derive(Clone)]
+pub struct GotifyNotifier {
+    server_url: Url,
+    token: String,
+    priority: u8,
+    recovery_priority: u8,
+
+    client: Client,
+}
+
+/// Body of the server's error responses.
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+    #[serde(rename = "errorDescription")]
+    error_description: Option<String>,
+}
+
+impl GotifyNotifier {
+    pub fn new(settings: &GotifySettings) -> Result<Self, DodemansknopError> {
+        if settings.token.trim().is_empty() {
+            return Err(DodemansknopError::Config("gotify token must not be empty".to_string()));
+        }
+
+        let server_url = Url::parse(&settings.server_url)
+            .ok()
+            .filter(|url| !url.cannot_be_a_base())
+            .ok_or_else(|| DodemansknopError::Config(format!("invalid gotify server_url '{}'", settings.server_url)))?;
+
+        Ok(Self {
+            server_url,
+            token: settings.token.clone(),
+            priority: settings.priority,
+            recovery_priority: settings.recovery_priority,
+            client: Client::new(),
+        })
+    }
+
+    /// Builds the URL of an endpoint below the server URL, which may itself
+    /// have a path if Gotify is served behind a reverse proxy.
+    fn endpoint(&self, segment: &str) -> Url {
+        let mut url = self.server_url.clone();
+        url.path_segments_mut()
+            .expect("server url is checked to be a base")
+            .pop_if_empty()
+            .push(segment);
+        url
+    }
+
+    async fn send(&self, title: String, message: String, priority: u8) -> Result<(), DodemansknopError> {
+        let mut url = self.endpoint("message");
+        url.query_pairs_mut().append_pair("token", &self.token);
+
+        let body = json!({
+            "title": title,
+            "message": message,
+            "priority": priority,
+        });
+
+        debug!("sending gotify message with priority {}", priority);
+
+        // The request URL contains the application token, so neither the
+        // request nor errors that refer to its URL are logged.
+        let res = self.client
+            .request(Method::POST, url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| DodemansknopError::Transport(format!("could not send gotify request: {}", e.without_url())))?;
+
+        debug!("gotify server responded with {}", res.status());
+
+        Self::check(res).await
+    }
+
+    /// Maps an unsuccessful response to an error, including the description
+    /// from the server's error JSON if there is one.
+    async fn check(res: Response) -> Result<(), DodemansknopError> {
+        let status = res.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let text = res.text().await.unwrap_or_default();
+        let text = match serde_json::from_str::<ErrorResponse>(&text) {
+            Ok(ErrorResponse { error, error_description: Some(description) }) => format!("{}: {}", error, description),
+            Ok(ErrorResponse { error, error_description: None }) => error,
+            Err(_) => text,
+        };
+
+        Err(DodemansknopError::from_status("gotify", status, &text))
+    }
+}
+
+#[async_trait]
+impl Notifier for GotifyNotifier {
+    /// Calls the unauthenticated health endpoint. Gotify offers no way to
+    /// check an application token without sending a message, so the token
+    /// is only checked for being present.
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        let res = self.client
+            .request(Method::GET, self.endpoint("health"))
+            .send()
+            .await
+            .map_err(|e| DodemansknopError::Transport(format!("gotify server is not reachable: {}", e)))?;
+
+        Self::check(res).await
+    }
+
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let title = format!("{} missed its dead mans switch", alert.id);
+        let message = alert.message.clone().unwrap_or_else(|| format!("Service {} missed its dead mans switch; it {}", alert.id, alert.last_ping_description()));
+
+        self.send(title, message, self.priority).await
+    }
+
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let title = format!("{} recovered", alert.id);
+        let message = alert.message.clone().unwrap_or_else(|| format!("Service {} pinged its dead mans switch again", alert.id));
+
+        self.send(title, message, self.recovery_priority).await
+    }
+}
@@ -0,0 +1,154 @@
+use async_trait::async_trait;
+use log::debug;
+use reqwest::{Client, Method, Response, Url};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::config::MatrixSettings;
+use crate::error::DodemansknopError;
+use crate::notifier::{Alert, Notifier};
+
+/// Notifier that posts a message to a Matrix room, using the client-server
+/// API of a homeserver.
+#[derive(Clone)]
+pub struct MatrixNotifier {
+    homeserver_url: Url,
+    access_token: String,
+    room_id: String,
+    html: bool,
+
+    client: Client,
+}
+
+/// Body of the homeserver's error responses.
+#[derive(Deserialize)]
+struct ErrorResponse {
+    errcode: String,
+    error: Option<String>,
+}
+
+impl MatrixNotifier {
+    pub fn new(settings: &MatrixSettings) -> Result<Self, DodemansknopError> {
+        if settings.access_token.trim().is_empty() {
+            return Err(DodemansknopError::Config("matrix access_token must not be empty".to_string()));
+        }
+
+        if settings.room_id.trim().is_empty() {
+            return Err(DodemansknopError::Config("matrix room_id must not be empty".to_string()));
+        }
+
+        let homeserver_url = Url::parse(&settings.homeserver_url)
+            .ok()
+            .filter(|url| !url.cannot_be_a_base())
+            .ok_or_else(|| DodemansknopError::Config(format!("invalid matrix homeserver_url '{}'", settings.homeserver_url)))?;
+
+        Ok(Self {
+            homeserver_url,
+            access_token: settings.access_token.clone(),
+            room_id: settings.room_id.clone(),
+            html: settings.html,
+            client: Client::new(),
+        })
+    }
+
+    /// Builds the URL of a client-server API endpoint below the homeserver
+    /// URL, percent-encoding the segments where necessary.
+    fn endpoint(&self, segments: &[&str]) -> Url {
+        let mut url = self.homeserver_url.clone();
+        url.path_segments_mut()
+            .expect("homeserver url is checked to be a base")
+            .pop_if_empty()
+            .extend(["_matrix", "client", "v3"])
+            .extend(segments);
+        url
+    }
+
+    /// Maps an unsuccessful response to an error, including the error code
+    /// from the homeserver's error JSON if there is one.
+    async fn check(res: Response) -> Result<(), DodemansknopError> {
+        let status = res.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let text = res.text().await.unwrap_or_default();
+        let text = match serde_json::from_str::<ErrorResponse>(&text) {
+            Ok(ErrorResponse { errcode, error: Some(error) }) => format!("{}: {}", errcode, error),
+            Ok(ErrorResponse { errcode, error: None }) => errcode,
+            Err(_) => text,
+        };
+
+        Err(DodemansknopError::from_status("matrix", status, &text))
+    }
+
+    async fn send(&self, body: Value) -> Result<(), DodemansknopError> {
+        // The homeserver uses the transaction ID to deduplicate retried
+        // requests, so every message needs a new one.
+        let txn_id = uuid::Uuid::new_v4().to_string();
+        let url = self.endpoint(&["rooms", &self.room_id, "send", "m.room.message", &txn_id]);
+
+        debug!("sending matrix message to room {}", self.room_id);
+
+        // The access token is sent as a header, so the request is not logged.
+        let res = self.client
+            .request(Method::PUT, url)
+            .bearer_auth(&self.access_token)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| DodemansknopError::Transport(format!("could not send matrix request: {}", e)))?;
+
+        debug!("matrix homeserver responded with {}", res.status());
+
+        Self::check(res).await
+    }
+}
+
+#[async_trait]
+impl Notifier for MatrixNotifier {
+    /// Calls `whoami`, which verifies the access token.
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        let res = self.client
+            .request(Method::GET, self.endpoint(&["account", "whoami"]))
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .map_err(|e| DodemansknopError::Transport(format!("matrix homeserver is not reachable: {}", e)))?;
+
+        Self::check(res).await
+    }
+
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        // Custom messages are sent as plain text, as they are not markup.
+        if let Some(ref message) = alert.message {
+            return self.send(json!({ "msgtype": "m.text", "body": format!("\u{1F6A8} {}", message) })).await;
+        }
+
+        let since = alert.last_ping_description();
+        let text = format!("\u{1F6A8} Service {} missed its dead mans switch; it {}", alert.id, since);
+        let mut body = json!({
+            "msgtype": "m.text",
+            "body": text,
+        });
+
+        if self.html {
+            let id = alert.id
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;");
+
+            body["format"] = json!("org.matrix.custom.html");
+            body["formatted_body"] = json!(format!("\u{1F6A8} Service <b>{}</b> missed its dead mans switch; it {}", id, since));
+        }
+
+        self.send(body).await
+    }
+
+    /// Recoveries are only reported for checks with a recovery message.
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        match alert.message {
+            Some(ref message) => self.send(json!({ "msgtype": "m.text", "body": format!("\u{2705} {}", message) })).await,
+            None => Ok(()),
+        }
+    }
+}
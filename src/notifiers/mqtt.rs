@@ -0,0 +1,165 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use log::{debug, warn};
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+
+use crate::config::MqttSettings;
+use crate::error::DodemansknopError;
+use crate::notifier::{Alert, Heartbeat, HeartbeatPayload, Notifier};
+
+/// Number of messages that may be waiting to be sent to the broker. Further
+/// publishes fail until the connection catches up.
+const REQUEST_CAPACITY: usize = 32;
+
+/// Time to wait before reconnecting after the connection to the broker was
+/// lost.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Notifier that publishes a JSON message per alert to an MQTT broker.
+pub struct MqttNotifier {
+    host: String,
+    port: u16,
+    options: MqttOptions,
+    topic: String,
+    heartbeat_topic: String,
+    qos: QoS,
+
+    /// Client and the task driving its connection; both are set up on the
+    /// first publish, as the task needs a running runtime.
+    connection: OnceLock<(AsyncClient, JoinHandle<()>)>,
+}
+
+impl MqttNotifier {
+    pub fn new(settings: &MqttSettings) -> Result<Self, DodemansknopError> {
+        if settings.host.trim().is_empty() {
+            return Err(DodemansknopError::Config("mqtt host must not be empty".to_string()));
+        }
+
+        if settings.topic.trim().is_empty() {
+            return Err(DodemansknopError::Config("mqtt topic must not be empty".to_string()));
+        }
+
+        let qos = rumqttc::qos(settings.qos)
+            .map_err(|_| DodemansknopError::Config(format!("unsupported mqtt qos {}; must be 0, 1 or 2", settings.qos)))?;
+
+        let client_id = settings.client_id.clone()
+            .unwrap_or_else(|| format!("dodemansknop-{}", uuid::Uuid::new_v4()));
+
+        let mut options = MqttOptions::new(client_id, settings.host.clone(), settings.port);
+        options.set_keep_alive(Duration::from_secs(30));
+
+        if let Some(ref username) = settings.username {
+            options.set_credentials(username.clone(), settings.password.clone().unwrap_or_default());
+        }
+
+        Ok(Self {
+            host: settings.host.clone(),
+            port: settings.port,
+            options,
+            topic: settings.topic.clone(),
+            heartbeat_topic: settings.heartbeat_topic.clone(),
+            qos,
+            connection: OnceLock::new(),
+        })
+    }
+
+    fn client(&self) -> &AsyncClient {
+        let (client, _) = self.connection.get_or_init(|| {
+            let (client, mut eventloop) = AsyncClient::new(self.options.clone(), REQUEST_CAPACITY);
+            let broker = format!("{}:{}", self.host, self.port);
+
+            // The event loop sends queued messages and reconnects whenever
+            // it is polled after an error.
+            let task = tokio::spawn(async move {
+                loop {
+                    match eventloop.poll().await {
+                        Ok(Event::Incoming(Packet::ConnAck(_))) => debug!("connected to mqtt broker {}", broker),
+                        Ok(_) => {},
+                        Err(e) => {
+                            warn!("connection to mqtt broker {} failed: {}", broker, e);
+                            tokio::time::sleep(RECONNECT_DELAY).await;
+                        },
+                    }
+                }
+            });
+
+            (client, task)
+        });
+
+        client
+    }
+
+    fn render_topic(&self, alert: &Alert) -> String {
+        self.topic
+            .replace("{id}", &alert.id)
+            .replace("{event}", alert.event.as_str())
+            .replace("{severity}", alert.severity.as_str())
+    }
+
+    fn publish_alert(&self, alert: &Alert) -> Result<(), DodemansknopError> {
+        let payload = json!({
+            "id": alert.id,
+            "event": alert.event.as_str(),
+            "severity": alert.severity.as_str(),
+            "labels": alert.labels,
+            "payload": alert.payload,
+            "timestamp": alert.timestamp.to_rfc3339(),
+        });
+
+        self.publish(self.render_topic(alert), payload)
+    }
+
+    fn publish(&self, topic: String, payload: Value) -> Result<(), DodemansknopError> {
+        debug!("publishing to mqtt topic {}: {}", topic, payload);
+
+        // Publishing only queues the message for the event loop; waiting for
+        // room in the queue would hold up the alerter while the broker is
+        // unreachable.
+        self.client()
+            .try_publish(topic, self.qos, false, payload.to_string())
+            .map_err(|e| DodemansknopError::Transport(format!("could not publish mqtt message: {}", e)))
+    }
+}
+
+impl Drop for MqttNotifier {
+    /// Stops the connection task, e.g. when the notifier is replaced by a
+    /// configuration reload.
+    fn drop(&mut self) {
+        if let Some((_, task)) = self.connection.get() {
+            task.abort();
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for MqttNotifier {
+    /// Only checks that the broker accepts TCP connections.
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        let connect = TcpStream::connect((self.host.as_str(), self.port));
+
+        match tokio::time::timeout(Duration::from_secs(10), connect).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(DodemansknopError::Transport(format!("mqtt broker {}:{} is not reachable: {}", self.host, self.port, e))),
+            Err(_) => Err(DodemansknopError::Transport(format!("mqtt broker {}:{} did not respond in time", self.host, self.port))),
+        }
+    }
+
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.publish_alert(&alert)
+    }
+
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.publish_alert(&alert)
+    }
+
+    async fn notify_heartbeat(&self, heartbeat: &Heartbeat) -> Result<(), DodemansknopError> {
+        let payload = json!(HeartbeatPayload::new(heartbeat));
+
+        self.publish(self.heartbeat_topic.clone(), payload)
+    }
+}
@@ -0,0 +1,151 @@
+use async_trait::async_trait;
+use log::debug;
+use reqwest::{Client, Method, RequestBuilder, Response, Url};
+use serde::Deserialize;
+
+use crate::config::NtfySettings;
+use crate::error::DodemansknopError;
+use crate::notifier::{Alert, Notifier};
+
+/// Notifier that publishes a message to a topic of an ntfy server (ntfy.sh
+/// or a self-hosted instance), which pushes it to the topic's subscribers.
+#[derive(Clone)]
+pub struct NtfyNotifier {
+    server_url: Url,
+    topic: String,
+    auth: Auth,
+    priority: u8,
+    recovery_priority: u8,
+
+    client: Client,
+}
+
+#[derive(Clone)]
+enum Auth {
+    None,
+    Token(String),
+    Basic(String, String),
+}
+
+/// Body of the server's error responses.
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+impl NtfyNotifier {
+    pub fn new(settings: &NtfySettings) -> Result<Self, DodemansknopError> {
+        if settings.topic.trim().is_empty() {
+            return Err(DodemansknopError::Config("ntfy topic must not be empty".to_string()));
+        }
+
+        let server_url = Url::parse(&settings.server_url)
+            .ok()
+            .filter(|url| !url.cannot_be_a_base())
+            .ok_or_else(|| DodemansknopError::Config(format!("invalid ntfy server_url '{}'", settings.server_url)))?;
+
+        let auth = match (&settings.token, &settings.username, &settings.password) {
+            (Some(token), None, None) => Auth::Token(token.clone()),
+            (None, Some(username), Some(password)) => Auth::Basic(username.clone(), password.clone()),
+            (None, None, None) => Auth::None,
+            _ => return Err(DodemansknopError::Config("ntfy requires either a token or both username and password".to_string())),
+        };
+
+        Ok(Self {
+            server_url,
+            topic: settings.topic.clone(),
+            auth,
+            priority: settings.priority,
+            recovery_priority: settings.recovery_priority,
+            client: Client::new(),
+        })
+    }
+
+    /// Builds the URL of an endpoint below the server URL, which may itself
+    /// have a path if ntfy is served behind a reverse proxy.
+    fn endpoint(&self, segments: &[&str]) -> Url {
+        let mut url = self.server_url.clone();
+        url.path_segments_mut()
+            .expect("server url is checked to be a base")
+            .pop_if_empty()
+            .extend(segments);
+        url
+    }
+
+    fn authenticate(&self, rb: RequestBuilder) -> RequestBuilder {
+        match self.auth {
+            Auth::None => rb,
+            Auth::Token(ref token) => rb.bearer_auth(token),
+            Auth::Basic(ref username, ref password) => rb.basic_auth(username, Some(password)),
+        }
+    }
+
+    async fn send(&self, alert: &Alert, title: String, message: String, priority: u8, tag: &str) -> Result<(), DodemansknopError> {
+        let tags = format!("{},{}", tag, alert.severity.as_str());
+
+        debug!("publishing ntfy message with priority {} to topic {}", priority, self.topic);
+
+        let rb = self.client
+            .request(Method::POST, self.endpoint(&[&self.topic]))
+            .header("Title", title)
+            .header("Priority", priority.to_string())
+            .header("Tags", tags)
+            .body(message);
+
+        let res = self.authenticate(rb)
+            .send()
+            .await
+            .map_err(|e| DodemansknopError::Transport(format!("could not send ntfy request: {}", e)))?;
+
+        debug!("ntfy server responded with {}", res.status());
+
+        Self::check(res).await
+    }
+
+    /// Maps an unsuccessful response to an error, including the message from
+    /// the server's error JSON if there is one.
+    async fn check(res: Response) -> Result<(), DodemansknopError> {
+        let status = res.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let text = res.text().await.unwrap_or_default();
+        let text = match serde_json::from_str::<ErrorResponse>(&text) {
+            Ok(ErrorResponse { error }) => error,
+            Err(_) => text,
+        };
+
+        Err(DodemansknopError::from_status("ntfy", status, &text))
+    }
+}
+
+#[async_trait]
+impl Notifier for NtfyNotifier {
+    /// Calls the health endpoint. Whether the credentials allow publishing
+    /// to the topic can only be checked by publishing, so they are not
+    /// checked.
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        let res = self.client
+            .request(Method::GET, self.endpoint(&["v1", "health"]))
+            .send()
+            .await
+            .map_err(|e| DodemansknopError::Transport(format!("ntfy server is not reachable: {}", e)))?;
+
+        Self::check(res).await
+    }
+
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let title = format!("{} missed its dead mans switch", alert.id);
+        let message = alert.message.clone().unwrap_or_else(|| format!("Service {} missed its dead mans switch; it {}", alert.id, alert.last_ping_description()));
+
+        self.send(&alert, title, message, self.priority, "rotating_light").await
+    }
+
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let title = format!("{} recovered", alert.id);
+        let message = alert.message.clone().unwrap_or_else(|| format!("Service {} pinged its dead mans switch again", alert.id));
+
+        self.send(&alert, title, message, self.recovery_priority, "white_check_mark").await
+    }
+}
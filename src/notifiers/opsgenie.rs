@@ -0,0 +1,102 @@
+use async_trait::async_trait;
+use log::debug;
+use reqwest::{Client, Method, Url};
+use serde_json::{json, Value};
+
+use crate::error::DodemansknopError;
+use crate::notifier::{self, Alert, Notifier};
+
+const US_API_URL: &str = "https://api.opsgenie.com/v2/alerts";
+const EU_API_URL: &str = "https://api.eu.opsgenie.com/v2/alerts";
+
+/// Notifier that creates (and closes) Opsgenie alerts using the Alert API.
+/// The check ID is used as alias, so that the recovery of a check closes the
+/// alert created by its failure, and repeated failures are deduplicated by
+/// Opsgenie.
+#[derive(Clone)]
+pub struct OpsgenieNotifier {
+    api_key: String,
+    api_url: &'static str,
+
+    client: Client,
+}
+
+impl OpsgenieNotifier {
+    pub fn new(api_key: String, region: String) -> Result<Self, DodemansknopError> {
+        if api_key.trim().is_empty() {
+            return Err(DodemansknopError::Config("opsgenie api_key must not be empty".to_string()));
+        }
+
+        let api_url = match region.to_lowercase().as_str() {
+            "us" => US_API_URL,
+            "eu" => EU_API_URL,
+            r => return Err(DodemansknopError::Config(format!("unsupported opsgenie region '{}'; must be 'us' or 'eu'", r))),
+        };
+
+        Ok(Self {
+            api_key,
+            api_url,
+            client: Client::new(),
+        })
+    }
+
+    async fn send(&self, url: Url, body: Value) -> Result<(), DodemansknopError> {
+        let req = self.client
+            .request(Method::POST, url)
+            .header("authorization", format!("GenieKey {}", self.api_key))
+            .json(&body)
+            .build()
+            .map_err(|e| DodemansknopError::Config(format!("could not build opsgenie request: {}", e)))?;
+
+        debug!("executing request: {:?}", req);
+
+        let res = self.client.execute(req).await
+            .map_err(|e| DodemansknopError::Transport(format!("could not send opsgenie request: {}", e)))?;
+        debug!("response: {:?}", res);
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_default();
+            return Err(DodemansknopError::from_status("opsgenie", status, &text));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for OpsgenieNotifier {
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        notifier::probe(&self.client, "opsgenie", self.api_url).await
+    }
+
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let mut body = json!({
+            "message": format!("Service {} missed its dead mans switch", alert.id),
+            "alias": alert.id,
+            "source": "dodemansknop",
+            "details": alert.labels,
+        });
+
+        if let Some(ref payload) = alert.payload {
+            body["description"] = json!(format!("Last payload:\n{}", payload));
+        }
+
+        let url = Url::parse(self.api_url).map_err(|e| DodemansknopError::Config(e.to_string()))?;
+        self.send(url, body).await
+    }
+
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let mut url = Url::parse(self.api_url).map_err(|e| DodemansknopError::Config(e.to_string()))?;
+
+        // Pushing the alias as a path segment percent-encodes characters that
+        // are not allowed in a URL path.
+        url.path_segments_mut()
+            .map_err(|_| DodemansknopError::Config("invalid opsgenie api url".to_string()))?
+            .push(&alert.id)
+            .push("close");
+        url.query_pairs_mut().append_pair("identifierType", "alias");
+
+        self.send(url, json!({ "source": "dodemansknop" })).await
+    }
+}
@@ -0,0 +1,89 @@
+use async_trait::async_trait;
+use log::debug;
+use reqwest::Client;
+use reqwest::{Method, StatusCode};
+use serde_json::{json, Value};
+
+use crate::error::DodemansknopError;
+use crate::notifier::{self, Alert, Notifier};
+
+const EVENTS_API_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+/// Notifier that opens (and resolves) PagerDuty incidents using the Events
+/// API v2. The check ID is used as dedup key, so that the recovery of a check
+/// resolves the incident opened by its failure.
+#[derive(Clone)]
+pub struct PagerDutyNotifier {
+    routing_key: String,
+    severity: Option<String>,
+    source: String,
+
+    client: Client,
+}
+
+impl PagerDutyNotifier {
+    pub fn new(routing_key: String, severity: Option<String>, source: String) -> Self {
+        Self {
+            routing_key,
+            severity,
+            source,
+            client: Client::new(),
+        }
+    }
+
+    async fn send(&self, body: Value) -> Result<(), DodemansknopError> {
+        let req = self.client
+            .request(Method::POST, EVENTS_API_URL)
+            .json(&body)
+            .build()
+            .map_err(|e| DodemansknopError::Config(format!("could not build pagerduty request: {}", e)))?;
+
+        debug!("executing request: {:?}", req);
+
+        let res = self.client.execute(req).await
+            .map_err(|e| DodemansknopError::Transport(format!("could not send pagerduty request: {}", e)))?;
+        debug!("response: {:?}", res);
+
+        if res.status() != StatusCode::ACCEPTED {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_default();
+            return Err(DodemansknopError::from_status("pagerduty", status, &text));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for PagerDutyNotifier {
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        notifier::probe(&self.client, "pagerduty", EVENTS_API_URL).await
+    }
+
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let mut body = json!({
+            "routing_key": self.routing_key,
+            "event_action": "trigger",
+            "dedup_key": alert.id,
+            "payload": {
+                "summary": format!("Service {} missed its dead mans switch", alert.id),
+                "source": self.source,
+                "severity": self.severity.as_deref().unwrap_or(alert.severity.as_str()),
+            }
+        });
+
+        if let Some(ref payload) = alert.payload {
+            body["payload"]["custom_details"] = json!({ "last_payload": payload });
+        }
+
+        self.send(body).await
+    }
+
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.send(json!({
+            "routing_key": self.routing_key,
+            "event_action": "resolve",
+            "dedup_key": alert.id,
+        })).await
+    }
+}
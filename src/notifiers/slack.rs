@@ -1,65 +1,125 @@
+use async_trait::async_trait;
 use log::debug;
-use reqwest::blocking::Client;
+use reqwest::Client;
 use reqwest::Method;
-use serde::Serialize;
-use serde_json::json;
+use serde_json::{json, Value};
 
-use crate::notifier::{Alert, Notifier};
+use crate::config::Severity;
+use crate::error::DodemansknopError;
+use crate::notifier::{self, Alert, Heartbeat, Notifier};
 
 #[derive(Clone)]
 pub struct SlackNotifier {
     url: String,
     icon_emoji: String,
-    color: String,
+    color: Option<String>,
+    channel: Option<String>,
+    username: Option<String>,
 
     client: Client,
 }
 
-#[derive(Serialize)]
-struct SlackWebhookBody {
-    text: String,
-}
-
 impl SlackNotifier {
-    pub fn new(url: String, icon_emoji: String, color: String) -> Self {
+    pub fn new(url: String, icon_emoji: String, color: Option<String>, channel: Option<String>, username: Option<String>) -> Self {
         Self {
             url,
             icon_emoji,
             color,
+            channel,
+            username,
             client: Client::new(),
         }
     }
+
+    async fn post(&self, mut body: Value) -> Result<(), DodemansknopError> {
+        if let Some(ref channel) = self.channel {
+            body["channel"] = json!(channel);
+        }
+
+        if let Some(ref username) = self.username {
+            body["username"] = json!(username);
+        }
+
+        let req = self.client
+            .request(Method::POST, &self.url)
+            .json(&body)
+            .build()
+            .map_err(|e| DodemansknopError::Config(format!("could not build slack request: {}", e)))?;
+
+        debug!("executing request: {:?}", req);
+
+        let res = self.client.execute(req).await
+            .map_err(|e| DodemansknopError::Transport(format!("could not send slack request: {}", e)))?;
+        debug!("response: {:?}", res);
+
+        if !res.status().is_success() {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_default();
+            return Err(DodemansknopError::from_status("slack", status, &text));
+        }
+
+        Ok(())
+    }
 }
 
+fn severity_color(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "#439fe0",
+        Severity::Warning => "#ffa500",
+        Severity::Critical => "#ff0000",
+    }
+}
+
+#[async_trait]
 impl Notifier for SlackNotifier {
-    fn notify_failure(&self, alert: Alert) -> Result<(), &'static str> {
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        notifier::probe(&self.client, "slack", &self.url).await
+    }
+
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let (text, mut details) = match alert.message {
+            Some(ref message) => (
+                format!("{} {}", self.icon_emoji, message),
+                format!("*{} Dead Mans Switch missed*\n{}", self.icon_emoji, message),
+            ),
+            None => (
+                format!("{} Service *{}* missed its dead mans switch", self.icon_emoji, alert.id),
+                format!("*{} Dead Mans Switch missed*\nService {} missed its dead mans switch; it {}", self.icon_emoji, alert.id, alert.last_ping_description()),
+            ),
+        };
+
+        if let Some(ref payload) = alert.payload {
+            details.push_str(&format!("\nLast payload:\n```{}```", payload));
+        }
+
         let body = json!({
+            "text": text,
             "attachments": [{
-                "color": self.color,
+                "color": self.color.as_deref().unwrap_or_else(|| severity_color(alert.severity)),
                 "blocks": [
                     {
                         "type": "section",
                         "text": {
                             "type": "mrkdwn",
-                            "text": format!("**{} Dead Mans Switch missed**\nService {} missed its dead mans switch", self.icon_emoji, alert.id),
-                            "emoji": true
+                            "text": details,
                         }
                     }
                 ]
             }]
         });
 
-        let req = self.client
-            .request(Method::POST, &self.url)
-            .json(&body)
-            .build()
-            .unwrap();
-
-        debug!("executing request: {:?}", req);
+        self.post(body).await
+    }
 
-        let res = self.client.execute(req);
-        debug!("response: {:?}", res);
+    /// Recoveries are only reported for checks with a recovery message.
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        match alert.message {
+            Some(message) => self.post(json!({ "text": format!(":white_check_mark: {}", message) })).await,
+            None => Ok(()),
+        }
+    }
 
-        Ok(())
+    async fn notify_heartbeat(&self, heartbeat: &Heartbeat) -> Result<(), DodemansknopError> {
+        self.post(json!({ "text": format!(":heartbeat: {}", heartbeat.message()) })).await
     }
-}
\ No newline at end of file
+}
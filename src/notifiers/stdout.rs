@@ -0,0 +1,51 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::SecondsFormat;
+
+use crate::clock::Clock;
+use crate::error::DodemansknopError;
+use crate::notifier::{Alert, Heartbeat, Notifier};
+
+/// Notifier that prints a line per alert to stdout, e.g. to see alerts while
+/// developing locally. The log goes to stderr, so the two can be told apart.
+#[derive(Clone)]
+pub struct StdoutNotifier {
+    clock: Arc<dyn Clock>,
+}
+
+impl StdoutNotifier {
+    pub fn new(clock: Arc<dyn Clock>) -> Self {
+        Self { clock }
+    }
+
+    fn print_alert(&self, marker: &str, alert: &Alert) -> Result<(), DodemansknopError> {
+        self.print(marker, &format!("{} for {} (severity {})", alert.event.as_str().to_uppercase(), alert.id, alert.severity.as_str()))
+    }
+
+    fn print(&self, marker: &str, text: &str) -> Result<(), DodemansknopError> {
+        let line = format!("{} {} {}\n", self.clock.now().to_rfc3339_opts(SecondsFormat::Secs, true), marker, text);
+
+        // Unlike println!, this does not panic if stdout is closed.
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(line.as_bytes())
+            .and_then(|_| stdout.flush())
+            .map_err(|e| DodemansknopError::Transport(format!("could not write to stdout: {}", e)))
+    }
+}
+
+#[async_trait]
+impl Notifier for StdoutNotifier {
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.print_alert("[!!]", &alert)
+    }
+
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        self.print_alert("[ok]", &alert)
+    }
+
+    async fn notify_heartbeat(&self, heartbeat: &Heartbeat) -> Result<(), DodemansknopError> {
+        self.print("[<3]", &format!("HEARTBEAT {}", heartbeat.message()))
+    }
+}
@@ -0,0 +1,100 @@
+use std::io::Write;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use syslog::{Facility, Formatter3164, Logger, LoggerBackend};
+
+use crate::config::SyslogSettings;
+use crate::error::DodemansknopError;
+use crate::notifier::{Alert, AlertEvent, Notifier};
+
+/// Notifier that sends a syslog message per alert, either to the local
+/// syslog daemon or to a remote collector. Failures are logged with severity
+/// WARNING, recoveries with NOTICE.
+pub struct SyslogNotifier {
+    logger: Mutex<Logger<LoggerBackend, Formatter3164>>,
+
+    /// Whether messages go over TCP, which needs them to be terminated by a
+    /// newline, as the stream carries no other framing.
+    tcp: bool,
+}
+
+impl SyslogNotifier {
+    /// Connects to the syslog daemon or collector, so that an unreachable
+    /// one is noticed at startup.
+    pub fn new(settings: &SyslogSettings) -> Result<Self, DodemansknopError> {
+        let facility = Facility::from_str(&settings.facility)
+            .map_err(|_| DodemansknopError::Config(format!("unknown syslog facility '{}'", settings.facility)))?;
+
+        let formatter = Formatter3164 {
+            facility,
+            hostname: None,
+            process: "dodemansknop".to_string(),
+            pid: std::process::id(),
+        };
+
+        let tcp = settings.protocol == "tcp";
+        let logger = match settings.host {
+            None => syslog::unix(formatter)
+                .map_err(|e| DodemansknopError::Config(format!("could not connect to local syslog daemon: {}", describe(&e))))?,
+            Some(ref host) => {
+                let server = (host.as_str(), settings.port).to_socket_addrs()
+                    .ok()
+                    .and_then(|mut addrs| addrs.next())
+                    .ok_or_else(|| DodemansknopError::Config(format!("could not resolve syslog host '{}'", host)))?;
+
+                let logger = match settings.protocol.as_str() {
+                    "udp" => {
+                        let local: SocketAddr = if server.is_ipv4() { ([0, 0, 0, 0], 0).into() } else { ([0u16; 8], 0).into() };
+                        syslog::udp(formatter, local, server)
+                    },
+                    "tcp" => syslog::tcp(formatter, server),
+                    p => return Err(DodemansknopError::Config(format!("unsupported syslog protocol '{}'; must be 'udp' or 'tcp'", p))),
+                };
+
+                logger.map_err(|e| DodemansknopError::Config(format!("could not connect to syslog server {}: {}", server, describe(&e))))?
+            },
+        };
+
+        Ok(Self {
+            logger: Mutex::new(logger),
+            tcp,
+        })
+    }
+
+    fn send(&self, alert: &Alert, message: String) -> Result<(), DodemansknopError> {
+        let message = if self.tcp { message + "\n" } else { message };
+
+        let mut logger = self.logger.lock().unwrap();
+        let result = match alert.event {
+            AlertEvent::Failure => logger.warning(message),
+            AlertEvent::Recovery => logger.notice(message),
+        };
+
+        result
+            .map_err(|e| DodemansknopError::Transport(format!("could not send syslog message: {}", describe(&e))))
+            .and_then(|_| logger.backend.flush()
+                .map_err(|e| DodemansknopError::Transport(format!("could not send syslog message: {}", e))))
+    }
+}
+
+/// Describes a syslog error along with its causes, as the error itself only
+/// names the failed step.
+fn describe(e: &syslog::Error) -> String {
+    e.iter().map(|cause| cause.to_string()).collect::<Vec<_>>().join(": ")
+}
+
+#[async_trait]
+impl Notifier for SyslogNotifier {
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let message = format!("check {} missed its dead mans switch; it {}", alert.id, alert.last_ping_description());
+        self.send(&alert, message)
+    }
+
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let message = format!("check {} recovered", alert.id);
+        self.send(&alert, message)
+    }
+}
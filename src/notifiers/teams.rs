@@ -0,0 +1,89 @@
+use async_trait::async_trait;
+use log::debug;
+use reqwest::{Client, Method};
+use serde_json::json;
+
+use crate::error::DodemansknopError;
+use crate::notifier::{self, Alert, Notifier};
+
+/// Notifier that posts a MessageCard to a Microsoft Teams incoming webhook.
+#[derive(Clone)]
+pub struct TeamsNotifier {
+    url: String,
+    theme_color: String,
+
+    client: Client,
+}
+
+impl TeamsNotifier {
+    pub fn new(url: String, theme_color: String) -> Self {
+        Self {
+            url,
+            theme_color,
+            client: Client::new(),
+        }
+    }
+
+    async fn send(&self, title: &str, text: &str) -> Result<(), DodemansknopError> {
+        let body = json!({
+            "@type": "MessageCard",
+            "@context": "http://schema.org/extensions",
+            "themeColor": self.theme_color,
+            "summary": title,
+            "title": title,
+            "text": text,
+        });
+
+        let req = self.client
+            .request(Method::POST, &self.url)
+            .json(&body)
+            .build()
+            .map_err(|e| DodemansknopError::Config(format!("could not build teams request: {}", e)))?;
+
+        debug!("executing request: {:?}", req);
+
+        let res = self.client.execute(req).await
+            .map_err(|e| DodemansknopError::Transport(format!("could not send teams request: {}", e)))?;
+        debug!("response: {:?}", res);
+
+        // On success, Teams responds with the plain text "1" instead of
+        // JSON, so only the status is checked.
+        if !res.status().is_success() {
+            let status = res.status();
+            let text = res.text().await.unwrap_or_default();
+            return Err(DodemansknopError::from_status("teams", status, &text));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for TeamsNotifier {
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        notifier::probe(&self.client, "teams", &self.url).await
+    }
+
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let title = format!("Dead Mans Switch missed: {}", alert.id);
+
+        let mut text = match alert.message {
+            Some(ref message) => message.clone(),
+            None => format!("Service **{}** missed its dead mans switch; it {}.", alert.id, alert.last_ping_description()),
+        };
+
+        if let Some(ref payload) = alert.payload {
+            text.push_str(&format!("\n\nLast payload:\n\n{}", payload));
+        }
+
+        self.send(&title, &text).await
+    }
+
+    /// Recoveries are only reported for checks with a recovery message.
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        match alert.message {
+            Some(ref message) => self.send(&format!("Dead Mans Switch recovered: {}", alert.id), message).await,
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use async_trait::async_trait;
+use log::debug;
+use reqwest::{Client, Method};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::error::DodemansknopError;
+use crate::notifier::{Alert, Notifier};
+
+/// Notifier that sends a message to a Telegram chat using the Bot API.
+#[derive(Clone)]
+pub struct TelegramNotifier {
+    bot_token: String,
+    chat_id: String,
+    parse_mode: Option<ParseMode>,
+
+    client: Client,
+}
+
+#[derive(Clone, Copy)]
+enum ParseMode {
+    MarkdownV2,
+    Html,
+}
+
+impl ParseMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ParseMode::MarkdownV2 => "MarkdownV2",
+            ParseMode::Html => "HTML",
+        }
+    }
+}
+
+/// Every Bot API response carries `ok`; `description` explains failures.
+#[derive(Deserialize)]
+struct ApiResponse {
+    ok: bool,
+    description: Option<String>,
+}
+
+impl TelegramNotifier {
+    pub fn new(bot_token: String, chat_id: String, parse_mode: Option<String>) -> Result<Self, DodemansknopError> {
+        if bot_token.trim().is_empty() {
+            return Err(DodemansknopError::Config("telegram bot_token must not be empty".to_string()));
+        }
+
+        if chat_id.trim().is_empty() {
+            return Err(DodemansknopError::Config("telegram chat_id must not be empty".to_string()));
+        }
+
+        let parse_mode = match parse_mode.as_deref() {
+            None => None,
+            Some("MarkdownV2") => Some(ParseMode::MarkdownV2),
+            Some("HTML") => Some(ParseMode::Html),
+            Some(m) => return Err(DodemansknopError::Config(format!("unsupported telegram parse_mode '{}'; must be 'MarkdownV2' or 'HTML'", m))),
+        };
+
+        Ok(Self {
+            bot_token,
+            chat_id,
+            parse_mode,
+            client: Client::new(),
+        })
+    }
+
+    /// Escapes `text` so that it is displayed literally in the configured
+    /// parse mode.
+    fn escape(&self, text: &str) -> String {
+        match self.parse_mode {
+            None => text.to_string(),
+            Some(ParseMode::Html) => text
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+            Some(ParseMode::MarkdownV2) => text.chars().fold(String::new(), |mut escaped, c| {
+                if "_*[]()~`>#+-=|{}.!\\".contains(c) {
+                    escaped.push('\\');
+                }
+                escaped.push(c);
+                escaped
+            }),
+        }
+    }
+
+    async fn send(&self, text: String) -> Result<(), DodemansknopError> {
+        let mut body = json!({
+            "chat_id": self.chat_id,
+            "text": text,
+        });
+
+        if let Some(mode) = self.parse_mode {
+            body["parse_mode"] = json!(mode.as_str());
+        }
+
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", self.bot_token);
+
+        // The request URL contains the bot token, so neither the request nor
+        // errors that refer to its URL are logged.
+        debug!("sending telegram message to chat {}", self.chat_id);
+
+        let res = self.client
+            .request(Method::POST, url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| DodemansknopError::Transport(format!("could not send telegram request: {}", e.without_url())))?;
+
+        let status = res.status();
+        let api: ApiResponse = res.json().await
+            .map_err(|e| DodemansknopError::from_status("telegram", status, &format!("unreadable body: {}", e.without_url())))?;
+
+        debug!("telegram responded with {}", status);
+
+        if !api.ok {
+            return Err(DodemansknopError::from_status("telegram", status, &api.description.unwrap_or_default()));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    /// Calls `getMe`, which also verifies the bot token.
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        let url = format!("https://api.telegram.org/bot{}/getMe", self.bot_token);
+
+        let res = self.client
+            .request(Method::GET, url)
+            .send()
+            .await
+            .map_err(|e| DodemansknopError::Transport(format!("telegram endpoint is not reachable: {}", e.without_url())))?;
+
+        let status = res.status();
+        let api: ApiResponse = res.json().await
+            .map_err(|e| DodemansknopError::from_status("telegram", status, &format!("unreadable body: {}", e.without_url())))?;
+
+        if !api.ok {
+            return Err(DodemansknopError::from_status("telegram", status, &api.description.unwrap_or_default()));
+        }
+
+        Ok(())
+    }
+
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        if let Some(ref message) = alert.message {
+            return self.send(format!("\u{1F6A8} {}", self.escape(message))).await;
+        }
+
+        let id = self.escape(&alert.id);
+        let since = self.escape(&alert.last_ping_description());
+        let text = match self.parse_mode {
+            None => format!("\u{1F6A8} Service {} missed its dead mans switch; it {}", id, since),
+            Some(ParseMode::MarkdownV2) => format!("\u{1F6A8} Service *{}* missed its dead mans switch; it {}", id, since),
+            Some(ParseMode::Html) => format!("\u{1F6A8} Service <b>{}</b> missed its dead mans switch; it {}", id, since),
+        };
+
+        self.send(text).await
+    }
+
+    /// Recoveries are only reported for checks with a recovery message.
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        match alert.message {
+            Some(ref message) => self.send(format!("\u{2705} {}", self.escape(message))).await,
+            None => Ok(()),
+        }
+    }
+}
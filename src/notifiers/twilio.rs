@@ -0,0 +1,142 @@
+use async_trait::async_trait;
+use log::debug;
+use reqwest::{Client, Method, Response};
+use serde::Deserialize;
+
+use crate::config::TwilioSettings;
+use crate::error::DodemansknopError;
+use crate::notifier::{Alert, Notifier};
+
+const API_URL: &str = "https://api.twilio.com/2010-04-01";
+
+/// Maximum length of a message that fits into a single SMS segment, in
+/// (GSM-7) characters.
+const MAX_SEGMENT_LENGTH: usize = 160;
+
+/// Notifier that sends an SMS about failed checks to one or more phone
+/// numbers, using the Twilio Messages API.
+#[derive(Clone)]
+pub struct TwilioNotifier {
+    account_sid: String,
+    auth_token: String,
+    from: String,
+    to: Vec<String>,
+
+    client: Client,
+}
+
+/// Body of Twilio's error responses.
+#[derive(Deserialize)]
+struct ErrorResponse {
+    code: Option<u32>,
+    message: String,
+}
+
+impl TwilioNotifier {
+    pub fn new(settings: &TwilioSettings) -> Result<Self, DodemansknopError> {
+        if settings.account_sid.trim().is_empty() || settings.auth_token.trim().is_empty() {
+            return Err(DodemansknopError::Config("twilio account_sid and auth_token must not be empty".to_string()));
+        }
+
+        if settings.to.is_empty() {
+            return Err(DodemansknopError::Config("twilio requires at least one number in to".to_string()));
+        }
+
+        Ok(Self {
+            account_sid: settings.account_sid.clone(),
+            auth_token: settings.auth_token.clone(),
+            from: settings.from.clone(),
+            to: settings.to.clone(),
+            client: Client::new(),
+        })
+    }
+
+    /// Maps an unsuccessful response to an error, including the message
+    /// from Twilio's error JSON if there is one.
+    async fn check(res: Response) -> Result<(), DodemansknopError> {
+        let status = res.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let text = res.text().await.unwrap_or_default();
+        let text = match serde_json::from_str::<ErrorResponse>(&text) {
+            Ok(ErrorResponse { code: Some(code), message }) => format!("error {}: {}", code, message),
+            Ok(ErrorResponse { code: None, message }) => message,
+            Err(_) => text,
+        };
+
+        Err(DodemansknopError::from_status("twilio", status, &text))
+    }
+
+    async fn send(&self, to: &str, body: &str) -> Result<(), DodemansknopError> {
+        let url = format!("{}/Accounts/{}/Messages.json", API_URL, self.account_sid);
+
+        debug!("sending twilio message to {}", to);
+
+        // The request carries the auth token, so it is not logged.
+        let res = self.client
+            .request(Method::POST, url)
+            .basic_auth(&self.account_sid, Some(&self.auth_token))
+            .form(&[("To", to), ("From", self.from.as_str()), ("Body", body)])
+            .send()
+            .await
+            .map_err(|e| DodemansknopError::Transport(format!("could not send twilio request: {}", e.without_url())))?;
+
+        debug!("twilio responded with {}", res.status());
+
+        Self::check(res).await
+    }
+}
+
+/// Shortens `id` so that the message built around it fits into a single SMS
+/// segment.
+fn message(id: &str) -> String {
+    const SUFFIX: &str = " missed its dead mans switch";
+    let prefix = "ALERT: ";
+    let room = MAX_SEGMENT_LENGTH - prefix.len() - SUFFIX.len();
+
+    let id = if id.chars().count() > room {
+        let mut shortened: String = id.chars().take(room - 3).collect();
+        shortened.push_str("...");
+        shortened
+    } else {
+        id.to_string()
+    };
+
+    format!("{}{}{}", prefix, id, SUFFIX)
+}
+
+#[async_trait]
+impl Notifier for TwilioNotifier {
+    /// Fetches the account, which also verifies the credentials.
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        let url = format!("{}/Accounts/{}.json", API_URL, self.account_sid);
+
+        let res = self.client
+            .request(Method::GET, url)
+            .basic_auth(&self.account_sid, Some(&self.auth_token))
+            .send()
+            .await
+            .map_err(|e| DodemansknopError::Transport(format!("twilio endpoint is not reachable: {}", e.without_url())))?;
+
+        Self::check(res).await
+    }
+
+    /// Sends the message to all numbers, even if some of them fail; the
+    /// first error is returned.
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let body = message(&alert.id);
+        let mut result = Ok(());
+
+        for to in self.to.iter() {
+            if let Err(e) = self.send(to, &body).await {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+
+        result
+    }
+}
@@ -1,59 +1,256 @@
-use log::debug;
-use reqwest::blocking::Client;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use log::{debug, warn};
+use reqwest::{Client, Request};
+use reqwest::header::{HeaderValue, CONTENT_TYPE};
 use reqwest::Method;
+use serde::Serialize;
 use serde_json::{json, Value};
+use sha2::Sha256;
 
-use crate::notifier::{Notifier, Alert};
+use crate::config::{RetrySettings, WebhookSettings};
+use crate::error::DodemansknopError;
+use crate::notifier::{self, Notifier, Alert, AlertPayload, BatchPayload, Heartbeat, HeartbeatPayload};
 
 #[derive(Clone)]
 pub struct WebhookNotifier {
     url: String,
-    method: String,
+    method: Method,
     body: Option<Value>,
     headers: Vec<(String, String)>,
+    body_template: Option<String>,
+    content_type: HeaderValue,
+    timeout: Duration,
+    retry: RetrySettings,
+    signing_secret: Option<String>,
 
+    /// Created once and used for all requests (including retries), so that
+    /// repeated alerts reuse pooled keep-alive connections. Cloning it only
+    /// clones a handle to the same pool.
     client: Client,
 }
 
 impl WebhookNotifier {
-    pub fn new(url: String, method: String, body: Option<Value>, headers: Vec<(String, String)>) -> Self {
-        Self {
-            url,
-            method,
-            body,
-            headers,
-            client: Client::new(),
+    pub fn new(settings: &WebhookSettings) -> Result<Self, DodemansknopError> {
+        if let Some(ref t) = settings.body_template {
+            if t.trim().is_empty() {
+                return Err(DodemansknopError::Config("webhook body_template must not be empty".to_string()));
+            }
+        }
+
+        let method = parse_method(&settings.method)?;
+
+        // Alerts are always sent as request body, which many servers ignore
+        // on GET requests.
+        if method == Method::GET {
+            warn!("webhook {} uses method GET, but alerts are sent in the request body", settings.url);
         }
+
+        let content_type = HeaderValue::from_str(&settings.content_type)
+            .map_err(|e| DodemansknopError::Config(format!("invalid webhook content_type '{}': {}", settings.content_type, e)))?;
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(settings.timeout_seconds))
+            .build()
+            .map_err(|e| DodemansknopError::Config(format!("could not create webhook client: {}", e)))?;
+
+        Ok(Self {
+            url: settings.url.clone(),
+            method,
+            body: settings.body.clone(),
+            headers: settings.headers.clone().unwrap_or_default(),
+            body_template: settings.body_template.clone(),
+            content_type,
+            timeout: Duration::from_secs(settings.timeout_seconds),
+            retry: settings.retry,
+            signing_secret: settings.signing_secret.clone(),
+            client,
+        })
     }
-}
 
-impl Notifier for WebhookNotifier {
-    fn notify_failure(&self, alert: Alert) -> Result<(), &'static str> {
-        let method = match self.method.to_lowercase().as_str() {
-            "get" => Method::GET,
-            "post" => Method::POST,
-            "put" => Method::PUT,
-            _ => Method::GET,
+    fn render_template(template: &str, id: &str, timestamp: DateTime<Utc>, event: &str, severity: &str, payload: &str) -> String {
+        template
+            .replace("{id}", id)
+            .replace("{timestamp}", &timestamp.to_rfc3339())
+            .replace("{event}", event)
+            .replace("{severity}", severity)
+            .replace("{payload}", payload)
+    }
+
+    /// Returns the hex-encoded HMAC-SHA256 of `body`, keyed with `secret`.
+    fn sign(secret: &str, body: &[u8]) -> String {
+        // HMAC accepts keys of any length.
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    async fn send(&self, alert: Alert, message: String) -> Result<(), DodemansknopError> {
+        let body = match self.body_template {
+            Some(ref template) => Self::render_template(template, &alert.id, alert.timestamp, alert.event.as_str(), alert.severity.as_str(), alert.payload.as_deref().unwrap_or("")),
+            None => self.render_body(AlertPayload::new(&alert, message)),
         };
 
+        self.send_body(body).await
+    }
+
+    /// Renders `payload` as JSON into the configured body, which provides
+    /// defaults for fields that are not part of the payload.
+    fn render_body(&self, payload: impl Serialize) -> String {
         let mut msg = self.body.clone().unwrap_or(json!({}));
 
-        msg["id"] = json!(alert.id.clone());
-        msg["message"] = json!(format!("service {} missed its dead mans switch", alert.id));
+        if let Value::Object(fields) = json!(payload) {
+            for (key, value) in fields {
+                msg[key] = value;
+            }
+        }
+
+        msg.to_string()
+    }
+
+    fn failure_message(alert: &Alert) -> String {
+        format!("service {} missed its dead mans switch; it {}", alert.id, alert.last_ping_description())
+    }
 
-        let mut rb = self.client.request(method, &self.url).json(&msg);
+    async fn send_body(&self, body: String) -> Result<(), DodemansknopError> {
+        let mut rb = self.client.request(self.method.clone(), &self.url)
+            .header(CONTENT_TYPE, self.content_type.clone());
+
+        if let Some(ref secret) = self.signing_secret {
+            rb = rb.header("X-Signature", Self::sign(secret, body.as_bytes()));
+        }
+
+        rb = rb.body(body);
 
         for (header, value) in self.headers.iter() {
             rb = rb.header(header, value);
         }
 
-        let req = rb.build().unwrap();
+        let req = rb.build().map_err(|e| DodemansknopError::Config(format!("could not build webhook request: {}", e)))?;
+
+        self.execute_with_retry(req).await
+    }
+
+    async fn execute_with_retry(&self, req: Request) -> Result<(), DodemansknopError> {
+        let started = Instant::now();
+        let max_total = Duration::from_secs(self.retry.max_total_seconds);
+        let max_attempts = self.retry.max_attempts.max(1);
+        let mut delay = Duration::from_millis(self.retry.base_delay_ms);
+
+        let mut attempt = 1;
+        loop {
+            // Requests with an in-memory body can always be cloned.
+            let attempt_req = req.try_clone()
+                .ok_or_else(|| DodemansknopError::Config("could not clone webhook request".to_string()))?;
+
+            debug!("executing request (attempt {}/{}): {:?}", attempt, max_attempts, attempt_req);
 
-        debug!("executing request: {:?}", req);
+            let err = match self.attempt(attempt_req).await {
+                Ok(()) => return Ok(()),
+                Err(e) if e.is_retryable() => e,
+                Err(e) => return Err(e),
+            };
+
+            debug!("attempt {}/{} failed: {}", attempt, max_attempts, err);
+
+            if attempt >= max_attempts {
+                return Err(DodemansknopError::Transport(format!("giving up after {} attempts: {}", attempt, err)));
+            }
+
+            if started.elapsed() + delay > max_total {
+                return Err(DodemansknopError::Transport(format!("giving up after {} attempts, retry time exceeded: {}", attempt, err)));
+            }
+
+            tokio::time::sleep(delay).await;
+
+            // Unlike mul_f64, this does not panic for negative or overflowing
+            // results; such a delay exceeds the retry time anyway.
+            delay = Duration::try_from_secs_f64(delay.as_secs_f64() * self.retry.multiplier).unwrap_or(max_total);
+            attempt += 1;
+        }
+    }
+
+    async fn attempt(&self, req: Request) -> Result<(), DodemansknopError> {
+        let res = self.client.execute(req).await
+            .map_err(|e| if e.is_timeout() {
+                DodemansknopError::Transport(format!("webhook request timed out after {:?}", self.timeout))
+            } else {
+                DodemansknopError::Transport(format!("could not send webhook request: {}", e))
+            })?;
 
-        let res = self.client.execute(req);
         debug!("response: {:?}", res);
 
-        Ok(())
+        let status = res.status();
+        if status.is_server_error() || status.is_client_error() {
+            Err(DodemansknopError::from_status("webhook", status, ""))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Parses the configured HTTP method, ignoring case. Only methods that carry
+/// a request body (and GET, for receivers that expect it) are supported.
+fn parse_method(method: &str) -> Result<Method, DodemansknopError> {
+    match method.to_uppercase().as_str() {
+        "GET" => Ok(Method::GET),
+        "POST" => Ok(Method::POST),
+        "PUT" => Ok(Method::PUT),
+        _ => Err(DodemansknopError::Config(format!("unsupported webhook method '{}'; must be GET, POST or PUT", method))),
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn verify(&self) -> Result<(), DodemansknopError> {
+        notifier::probe(&self.client, "webhook", &self.url).await
+    }
+
+    async fn notify_failure(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let message = Self::failure_message(&alert);
+        self.send(alert, message).await
+    }
+
+    async fn notify_recovery(&self, alert: Alert) -> Result<(), DodemansknopError> {
+        let message = format!("service {} recovered", alert.id);
+        self.send(alert, message).await
     }
-}
\ No newline at end of file
+
+    /// Sends all failures in a single request, whose JSON body lists the
+    /// affected checks in `ids`, carries the highest of their severities and
+    /// the payload of each failure in `alerts`. With a `body_template`, one
+    /// request is sent per failure instead.
+    async fn notify_failures(&self, alerts: &[Alert]) -> Result<(), DodemansknopError> {
+        if self.body_template.is_some() {
+            let mut result = Ok(());
+            for alert in alerts {
+                if let Err(e) = self.notify_failure(alert.clone()).await {
+                    if result.is_ok() {
+                        result = Err(e);
+                    }
+                }
+            }
+            return result;
+        }
+
+        let ids: Vec<&str> = alerts.iter().map(|a| a.id.as_str()).collect();
+        let message = format!("{} services missed their dead mans switch: {}", ids.len(), ids.join(", "));
+
+        self.send_body(self.render_body(BatchPayload::new(alerts, message, Self::failure_message))).await
+    }
+
+    /// Sends the check counts along with `"event": "heartbeat"`. With a
+    /// `body_template`, the message is passed as `{payload}` and `{id}` is
+    /// left empty.
+    async fn notify_heartbeat(&self, heartbeat: &Heartbeat) -> Result<(), DodemansknopError> {
+        let body = match self.body_template {
+            Some(ref template) => Self::render_template(template, "", heartbeat.timestamp, "heartbeat", "info", &heartbeat.message()),
+            None => self.render_body(HeartbeatPayload::new(heartbeat)),
+        };
+
+        self.send_body(body).await
+    }
+}
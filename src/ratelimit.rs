@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Number of buckets above which idle buckets are discarded.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Per-key token bucket rate limiter. Each key may be used `rate` times per
+/// second on average, with bursts of up to `rate` uses (but at least one).
+/// Without a rate, every use is allowed.
+pub struct RateLimiter {
+    rate: Option<f64>,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub fn new(rate: Option<f64>) -> Self {
+        Self {
+            rate,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token from the bucket of `key`, returning `false` if the
+    /// bucket is empty.
+    pub fn check(&self, key: &str) -> bool {
+        let rate = match self.rate {
+            Some(r) => r,
+            None => return true,
+        };
+
+        let capacity = rate.max(1.0);
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        // Buckets that have been refilled completely are equivalent to
+        // missing ones, so they can be dropped to bound memory usage when
+        // clients use many different keys.
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|_, b| b.tokens + now.duration_since(b.updated).as_secs_f64() * rate < capacity);
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket { tokens: capacity, updated: now });
+
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * rate).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens < 1.0 {
+            return false;
+        }
+
+        bucket.tokens -= 1.0;
+        true
+    }
+}
+
+/// Per-key debouncer that lets through the first use of a key, and treats
+/// further uses within `window` of it as duplicates. With a zero window,
+/// nothing is a duplicate.
+pub struct Debouncer {
+    window: Duration,
+    first_uses: Mutex<HashMap<String, Instant>>,
+}
+
+impl Debouncer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            first_uses: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Records a use of `key`, returning `true` if it is a duplicate of one
+    /// within the window.
+    pub fn is_duplicate(&self, key: &str) -> bool {
+        if self.window.is_zero() {
+            return false;
+        }
+
+        let now = Instant::now();
+        let mut first_uses = self.first_uses.lock().unwrap();
+
+        if first_uses.len() >= PRUNE_THRESHOLD {
+            first_uses.retain(|_, t| now.duration_since(*t) < self.window);
+        }
+
+        match first_uses.get(key) {
+            Some(t) if now.duration_since(*t) < self.window => true,
+            _ => {
+                first_uses.insert(key.to_string(), now);
+                false
+            },
+        }
+    }
+
+    /// Forgets the last use of `key`, e.g. because it failed, so that the
+    /// next use is not taken for a duplicate.
+    pub fn forget(&self, key: &str) {
+        self.first_uses.lock().unwrap().remove(key);
+    }
+}
@@ -0,0 +1,312 @@
+use std::fs;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::clock::Clock;
+use crate::config::RetryQueueSettings;
+use crate::notifier::{Alert, AlertEvent};
+
+/// A notification that could not be delivered, and is waiting to be retried.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct QueueEntry {
+    pub key: String,
+
+    /// The alert to send. Its targets are narrowed down to the notifiers
+    /// that failed, so that the others are not notified twice.
+    pub alert: Alert,
+
+    pub first_failed: DateTime<Utc>,
+    pub attempts: u32,
+    pub next_attempt: DateTime<Utc>,
+
+    /// Whether a retry of this entry is currently being sent. In-flight
+    /// entries stay in the file, so that they are retried after a restart.
+    #[serde(skip)]
+    in_flight: bool,
+}
+
+/// Durable queue of notifications that failed with a temporary error. The
+/// queue is stored as JSON lines (one entry per line) and rewritten on every
+/// change; it is only used by the alerter task.
+pub struct RetryQueue {
+    settings: RetryQueueSettings,
+    entries: Vec<QueueEntry>,
+    clock: Arc<dyn Clock>,
+}
+
+impl RetryQueue {
+    /// Loads the queue from the configured file. A missing file results in
+    /// an empty queue; unparseable lines are skipped with a warning.
+    pub fn load(settings: RetryQueueSettings, clock: Arc<dyn Clock>) -> Self {
+        let entries = match fs::read_to_string(&settings.path) {
+            Ok(contents) => contents.lines()
+                .filter(|line| !line.trim().is_empty())
+                .filter_map(|line| match serde_json::from_str::<QueueEntry>(line) {
+                    Ok(mut entry) => {
+                        // Entries queued by older versions carry no timestamp.
+                        if entry.alert.timestamp == DateTime::<Utc>::default() {
+                            entry.alert.timestamp = entry.first_failed;
+                        }
+                        Some(entry)
+                    },
+                    Err(e) => {
+                        warn!("skipping invalid entry in retry queue {}: {}", settings.path, e);
+                        None
+                    }
+                })
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                warn!("could not read retry queue {}: {}; starting with empty queue", settings.path, e);
+                Vec::new()
+            }
+        };
+
+        Self { settings, entries, clock }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds a notification that failed for the first time.
+    pub fn push(&mut self, alert: Alert) {
+        let now = self.clock.now();
+
+        info!(id = alert.id, event = alert.event.as_str(); "queueing {} of {} for retry", alert.event.as_str(), alert.id);
+
+        self.entries.push(QueueEntry {
+            key: uuid::Uuid::new_v4().to_string(),
+            alert,
+            first_failed: now,
+            attempts: 1,
+            next_attempt: now + backoff(&self.settings, 1),
+            in_flight: false,
+        });
+        self.save();
+    }
+
+    /// Drops entries that exceeded the maximum age, and returns the entries
+    /// that are due for a retry, marking them as in flight.
+    pub fn take_due(&mut self) -> Vec<(String, Alert)> {
+        let now = self.clock.now();
+        let max_age = Duration::seconds(self.settings.max_age_seconds as i64);
+
+        let before = self.entries.len();
+        self.entries.retain(|entry| {
+            let expired = !entry.in_flight && now - entry.first_failed > max_age;
+            if expired {
+                warn!(id = entry.alert.id, event = entry.alert.event.as_str(); "dropping {} of {} from retry queue after {} attempts; it is older than {}s", entry.alert.event.as_str(), entry.alert.id, entry.attempts, max_age.num_seconds());
+            }
+            !expired
+        });
+
+        let due: Vec<(String, Alert)> = self.entries.iter_mut()
+            .filter(|entry| !entry.in_flight && entry.next_attempt <= now)
+            .map(|entry| {
+                entry.in_flight = true;
+                (entry.key.clone(), entry.alert.clone())
+            })
+            .collect();
+
+        if self.entries.len() != before {
+            self.save();
+        }
+
+        due
+    }
+
+    /// Removes an entry whose retry was delivered.
+    pub fn complete(&mut self, key: &str) {
+        self.entries.retain(|entry| entry.key != key);
+        self.save();
+    }
+
+    /// Schedules the next retry of an entry whose retry failed again;
+    /// `alert` carries the notifiers that still failed.
+    pub fn reschedule(&mut self, key: &str, alert: Alert) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.key == key) {
+            entry.alert = alert;
+            entry.attempts += 1;
+            entry.next_attempt = self.clock.now() + backoff(&self.settings, entry.attempts);
+            entry.in_flight = false;
+        }
+        self.save();
+    }
+
+    /// Drops queued failures of check `id` once it recovered, as they are
+    /// no longer relevant. Entries that are in flight are left alone.
+    pub fn discard_failures(&mut self, id: &str) {
+        let before = self.entries.len();
+        self.entries.retain(|entry| entry.in_flight || entry.alert.id != id || entry.alert.event != AlertEvent::Failure);
+
+        if self.entries.len() != before {
+            info!(id = id; "check {} recovered; dropped {} queued failure notifications", id, before - self.entries.len());
+            self.save();
+        }
+    }
+
+    /// Drops all entries, e.g. after all checks were reset. Retries that are
+    /// in flight are still sent, but their outcome is ignored.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.save();
+    }
+
+    /// Writes the queue to its file. As with the state file, the file is
+    /// replaced atomically.
+    fn save(&self) {
+        let tmp_path = format!("{}.tmp", self.settings.path);
+
+        let result = self.entries.iter()
+            .map(|entry| serde_json::to_string(entry).map(|line| line + "\n"))
+            .collect::<Result<String, _>>()
+            .map_err(|e| e.to_string())
+            .and_then(|contents| fs::write(&tmp_path, contents).map_err(|e| e.to_string()))
+            .and_then(|_| fs::rename(&tmp_path, &self.settings.path).map_err(|e| e.to_string()));
+
+        if let Err(e) = result {
+            warn!("could not write retry queue {}: {}", self.settings.path, e);
+        }
+    }
+}
+
+/// Delay before the retry that follows attempt number `attempts`, doubling
+/// with each attempt up to the configured maximum.
+fn backoff(settings: &RetryQueueSettings, attempts: u32) -> Duration {
+    let factor = 2u64.saturating_pow(attempts.saturating_sub(1));
+    let delay = settings.base_delay_seconds.saturating_mul(factor).min(settings.max_delay_seconds);
+    Duration::seconds(delay as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::time::Duration as StdDuration;
+
+    use super::*;
+    use crate::clock::FakeClock;
+    use crate::config::Severity;
+
+    /// A queue with retries after 10, 20 and then every 30 seconds, stored in
+    /// a new file in the temp directory.
+    fn queue(max_age_seconds: u64) -> RetryQueue {
+        let path = std::env::temp_dir().join(format!("dodemansknop-retries-{}.jsonl", uuid::Uuid::new_v4()));
+        let settings = RetryQueueSettings {
+            path: path.to_str().unwrap().to_string(),
+            base_delay_seconds: 10,
+            max_delay_seconds: 30,
+            max_age_seconds,
+        };
+
+        RetryQueue::load(settings, Arc::new(FakeClock::new("2026-01-05T10:00:00Z".parse().unwrap())))
+    }
+
+    fn alert(id: &str, event: AlertEvent) -> Alert {
+        Alert {
+            id: id.to_string(),
+            event,
+            targets: Some(vec!["webhook".to_string()]),
+            payload: None,
+            last_ping: None,
+            labels: HashMap::new(),
+            severity: Severity::Critical,
+            consecutive_misses: 1,
+            message: None,
+            timestamp: "2026-01-05T10:00:00Z".parse().unwrap(),
+        }
+    }
+
+    fn due_ids(queue: &mut RetryQueue) -> Vec<String> {
+        queue.take_due().into_iter().map(|(_, alert)| alert.id).collect()
+    }
+
+    async fn advance(seconds: u64) {
+        tokio::time::advance(StdDuration::from_secs(seconds)).await;
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_back_off_up_to_the_maximum_delay() {
+        let mut queue = queue(3600);
+        queue.push(alert("a", AlertEvent::Failure));
+        assert!(queue.take_due().is_empty());
+
+        for delay in [10, 20, 30, 30] {
+            advance(delay - 1).await;
+            assert!(queue.take_due().is_empty(), "retried before {}s", delay);
+
+            advance(1).await;
+            let due = queue.take_due();
+            assert_eq!(due.len(), 1, "not retried after {}s", delay);
+
+            let (key, alert) = due.into_iter().next().unwrap();
+            queue.reschedule(&key, alert);
+        }
+
+        let _ = fs::remove_file(&queue.settings.path);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn in_flight_entries_are_neither_retried_twice_nor_expired() {
+        let mut queue = queue(60);
+        queue.push(alert("a", AlertEvent::Failure));
+        queue.push(alert("b", AlertEvent::Failure));
+
+        advance(10).await;
+        let due = queue.take_due();
+        assert_eq!(due.len(), 2);
+        assert!(queue.take_due().is_empty());
+
+        // "a" failed again and waits for its next retry, while "b" is still
+        // being sent when both exceed the maximum age.
+        let (key, alert) = due.into_iter().find(|(_, alert)| alert.id == "a").unwrap();
+        queue.reschedule(&key, alert);
+        advance(51).await;
+
+        assert!(queue.take_due().is_empty());
+        assert_eq!(queue.entries.iter().map(|e| e.alert.id.as_str()).collect::<Vec<_>>(), vec!["b"]);
+
+        let _ = fs::remove_file(&queue.settings.path);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn recovered_checks_discard_only_their_waiting_failures() {
+        let mut queue = queue(3600);
+        queue.push(alert("a", AlertEvent::Failure));
+        advance(10).await;
+        assert_eq!(due_ids(&mut queue), vec!["a"]);
+
+        queue.push(alert("a", AlertEvent::Failure));
+        queue.push(alert("a", AlertEvent::Recovery));
+        queue.push(alert("b", AlertEvent::Failure));
+        queue.discard_failures("a");
+
+        let remaining: Vec<(&str, AlertEvent)> = queue.entries.iter().map(|e| (e.alert.id.as_str(), e.alert.event)).collect();
+        assert_eq!(remaining, vec![("a", AlertEvent::Failure), ("a", AlertEvent::Recovery), ("b", AlertEvent::Failure)]);
+        assert!(queue.entries[0].in_flight);
+
+        let _ = fs::remove_file(&queue.settings.path);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn entries_in_flight_at_a_restart_are_retried_after_it() {
+        let mut queue = queue(3600);
+        queue.push(alert("a", AlertEvent::Failure));
+        advance(10).await;
+        assert_eq!(due_ids(&mut queue), vec!["a"]);
+
+        let mut reloaded = RetryQueue::load(queue.settings.clone(), queue.clock.clone());
+        assert_eq!(reloaded.len(), 1);
+        assert_eq!(due_ids(&mut reloaded), vec!["a"]);
+        assert_eq!(reloaded.entries[0].alert.targets, Some(vec!["webhook".to_string()]));
+
+        let _ = fs::remove_file(&queue.settings.path);
+    }
+}
@@ -0,0 +1,926 @@
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use log::{debug, error, info, warn};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep_until, Instant};
+
+use crate::clock::Clock;
+use crate::config::{ResolvedCheck, Settings, Severity};
+use crate::error::DodemansknopError;
+use crate::events::{Event, EventKind, EventLog};
+use crate::metrics::Metrics;
+use crate::notifier::{Alert, AlertEvent, CompositeNotifier, Notifier, SharedNotifier};
+use crate::store::StateStore;
+use crate::state::{CheckCondition, CheckDetail, CheckSource, CheckState, CheckStatus, SharedState};
+
+/// Management commands sent to the ping receiver. Commands that concern a
+/// single check carry a reply channel that receives `false` if the check is
+/// not known.
+pub enum Command {
+    /// Cancel the check's timer and ignore its pings until resumed.
+    Pause { id: String, reply: oneshot::Sender<bool> },
+
+    /// Re-arm the check's timer as if a fresh ping had arrived.
+    Resume { id: String, reply: oneshot::Sender<bool> },
+
+    /// Silence further failure notifications of the check until it pings.
+    Acknowledge { id: String, reply: oneshot::Sender<bool> },
+
+    /// Reply with the state of all checks.
+    List { reply: oneshot::Sender<HashMap<String, CheckState>> },
+
+    /// Reply with the details of the check, or `None` if it is not known.
+    Describe { id: String, reply: oneshot::Sender<Option<CheckDetail>> },
+
+    /// Cancel the check's timer and forget about the check entirely.
+    Delete { id: String, reply: oneshot::Sender<bool> },
+
+    /// Replace the settings and notifiers with freshly loaded ones.
+    Reload { settings: Box<Settings>, notifier: Arc<CompositeNotifier> },
+
+    /// Send a synthetic failure to all notifiers, without touching any
+    /// check, and reply with the outcome.
+    TestNotification { reply: oneshot::Sender<Result<(), DodemansknopError>> },
+
+    /// Reply with the `limit` most recent events, optionally only those of
+    /// one check.
+    Events { id: Option<String>, limit: usize, reply: oneshot::Sender<Vec<Event>> },
+
+    /// Cancel all timers and forget about all checks, then declare the
+    /// configured checks again; reply with the number of checks forgotten.
+    Reset { reply: oneshot::Sender<usize> },
+}
+
+/// Messages from the ping receiver (and the timers it schedules) to the
+/// alerter task.
+pub enum AlerterMessage {
+    /// Send the alert, unless it is suppressed.
+    Alert(Alert),
+
+    /// A check that was alerted has been deleted. `recovery` resolves its
+    /// alert, and `group` is the group it belonged to, which can no longer
+    /// be looked up.
+    Deleted { recovery: Alert, group: Option<String> },
+
+    /// All checks were forgotten; forget about their alerts as well,
+    /// including the ones waiting to be retried.
+    Reset,
+}
+
+/// ID of the check that test notifications are sent for.
+pub const TEST_CHECK_ID: &str = "__test__";
+
+/// A ping received by the HTTP API.
+pub struct Ping {
+    pub id: String,
+
+    /// Correlation ID of the HTTP request that carried the ping.
+    pub request_id: String,
+
+    /// Body of the ping request, if it had one.
+    pub payload: Option<String>,
+
+    /// Receives the check's next deadline once the ping was processed, or
+    /// `None` if the check is not expected to ping again (e.g. because it
+    /// is paused). Dropped without a reply if the ping was ignored.
+    pub reply: Option<oneshot::Sender<Option<DateTime<Utc>>>>,
+}
+
+/// Everything a scheduled timeout needs to raise an alert.
+#[derive(Clone)]
+pub struct TimeoutContext {
+    tx_alert: mpsc::UnboundedSender<AlerterMessage>,
+    check_state: SharedState,
+    metrics: Arc<Metrics>,
+    clock: Arc<dyn Clock>,
+    store: Option<Arc<dyn StateStore>>,
+    events: Arc<EventLog>,
+}
+
+impl TimeoutContext {
+    pub fn new(tx_alert: mpsc::UnboundedSender<AlerterMessage>, check_state: SharedState, metrics: Arc<Metrics>, clock: Arc<dyn Clock>, store: Option<Arc<dyn StateStore>>, events: Arc<EventLog>) -> Self {
+        Self {
+            tx_alert,
+            check_state,
+            metrics,
+            clock,
+            store,
+            events,
+        }
+    }
+
+    fn record(&self, id: &str, event: EventKind, request_id: Option<&str>) {
+        self.events.record(Event {
+            timestamp: self.clock.now(),
+            id: id.to_string(),
+            event,
+            request_id: request_id.map(str::to_string),
+        });
+    }
+}
+
+/// Handle of a task that runs a check's timeout. The task is aborted when
+/// the handle is dropped, so replacing or removing a check's entry in
+/// `active_timers` cancels its pending alert.
+struct TimerHandle(JoinHandle<()>);
+
+impl Drop for TimerHandle {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Owns the timers of all checks. Only the ping receiver task touches it.
+struct Scheduler {
+    settings: Settings,
+    notifier: SharedNotifier,
+    ctx: TimeoutContext,
+    active_timers: HashMap<String, TimerHandle>,
+
+    /// End of `startup_grace_seconds`, counted from the start of the ping
+    /// receiver; declared checks that never pinged are not alerted before.
+    startup_grace_until: Option<DateTime<Utc>>,
+}
+
+pub fn run_ping_receiver_task(mut rx_ping: mpsc::Receiver<Ping>, mut rx_commands: mpsc::UnboundedReceiver<Command>, ctx: TimeoutContext, settings: Settings, notifier: SharedNotifier) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let startup_grace_until = settings.startup_grace_seconds
+            .and_then(|s| chrono::Duration::from_std(std::time::Duration::from_secs(s)).ok())
+            .and_then(|grace| ctx.clock.now().checked_add_signed(grace));
+
+        let mut scheduler = Scheduler {
+            ctx,
+            settings,
+            notifier,
+            active_timers: HashMap::new(),
+            startup_grace_until,
+        };
+
+        scheduler.restore();
+
+        loop {
+            tokio::select! {
+                // recv() only returns None after all senders (held by the
+                // HTTP server) have been dropped, which happens on shutdown.
+                ping = rx_ping.recv() => match ping {
+                    Some(ping) => scheduler.handle_ping(ping),
+                    None => break,
+                },
+                Some(cmd) = rx_commands.recv() => scheduler.handle_command(cmd),
+            }
+        }
+
+        info!("ping channel closed; cancelling {} active timers", scheduler.active_timers.len());
+    })
+}
+
+impl Scheduler {
+    /// Re-arms timers for all checks that were restored from the state file,
+    /// taking into account the time that elapsed since their last ping.
+    fn restore(&mut self) {
+        let restored: Vec<(String, CheckState)> = self.ctx.check_state.lock().unwrap()
+            .iter()
+            .map(|(id, state)| (id.clone(), state.clone()))
+            .collect();
+
+        for (id, state) in restored {
+            if state.status == CheckStatus::Alerted {
+                self.ctx.metrics.inc_checks_overdue();
+            }
+
+            let check = self.settings.check(&id);
+            let delay = match remaining_delay(&state, &check, self.ctx.clock.now()) {
+                Some(d) if state.status == CheckStatus::Healthy && state.last_ping.is_none() => self.within_startup_grace(d),
+                Some(d) => d,
+                None => continue,
+            };
+
+            debug!(id = id; "restored check {}; timeout is {}", id, delay);
+
+            self.arm(id, check, delay);
+        }
+
+        self.declare();
+        self.announce_startup_grace();
+    }
+
+    /// Extends `delay` of a declared check that never pinged to the end of
+    /// the startup grace, so that after a cold start its client has time to
+    /// report in before it is alerted.
+    fn within_startup_grace(&self, delay: chrono::Duration) -> chrono::Duration {
+        match self.startup_grace_until {
+            Some(until) => std::cmp::max(delay, until - self.ctx.clock.now()),
+            None => delay,
+        }
+    }
+
+    /// Logs that the startup grace is active, and again once it lifts.
+    fn announce_startup_grace(&self) {
+        let remaining = match self.startup_grace_until.and_then(|until| (until - self.ctx.clock.now()).to_std().ok()) {
+            Some(remaining) if !remaining.is_zero() => remaining,
+            _ => return,
+        };
+
+        info!("startup grace is active; declared checks that never pinged are not alerted for {} seconds", remaining.as_secs_f64().ceil());
+
+        tokio::spawn(async move {
+            tokio::time::sleep(remaining).await;
+            info!("startup grace has ended; declared checks that never pinged are alerted as usual");
+        });
+    }
+
+    /// Marks the checks listed in the settings as declared and all others as
+    /// discovered, and applies their configured labels, groups and
+    /// dependencies. Declared checks that are not known yet are armed as if
+    /// they had pinged just now, so that a check that never pings at all
+    /// still raises an alert.
+    fn declare(&mut self) {
+        let mut new_checks = Vec::new();
+
+        {
+            let mut checks = self.ctx.check_state.lock().unwrap();
+
+            for (id, state) in checks.iter_mut() {
+                let check = self.settings.check(id);
+                state.source = self.source(id);
+                state.labels = check.labels;
+                state.group = check.group;
+                state.depends_on = check.depends_on;
+            }
+
+            for id in self.settings.declared_ids() {
+                if checks.contains_key(id) {
+                    continue;
+                }
+
+                let check = self.settings.check(id);
+                checks.insert(id.clone(), CheckState {
+                    status: CheckStatus::Healthy,
+                    last_ping: None,
+                    last_payload: None,
+                    source: CheckSource::Declared,
+                    labels: check.labels,
+                    group: check.group,
+                    depends_on: check.depends_on,
+                    consecutive_misses: 0,
+                    acknowledged: false,
+                    paused_while_alerted: false,
+                });
+                new_checks.push(id.clone());
+            }
+
+            if let Some(ref store) = self.ctx.store {
+                store.save_all(&checks);
+            }
+        }
+
+        for id in new_checks {
+            let check = self.settings.check(&id);
+            let now = self.ctx.clock.now();
+            if let Some(delay) = check.delay_until_deadline(now, now) {
+                let delay = self.within_startup_grace(delay);
+                debug!(id = id; "armed declared check {}; timeout is {}", id, delay);
+                self.arm(id, check, delay);
+            }
+        }
+    }
+
+    fn source(&self, id: &str) -> CheckSource {
+        if self.settings.is_declared(id) {
+            CheckSource::Declared
+        } else {
+            CheckSource::Discovered
+        }
+    }
+
+    /// Whether tracking the unknown check `id` would exceed `max_checks`.
+    /// The HTTP API already rejects such pings, but may let a few through
+    /// when several arrive at once.
+    fn exceeds_max_checks(&self, checks: &HashMap<String, CheckState>, id: &str) -> bool {
+        match self.settings.server.max_checks {
+            Some(max) if self.source(id) == CheckSource::Discovered => {
+                checks.values().filter(|c| c.source == CheckSource::Discovered).count() >= max
+            },
+            _ => false,
+        }
+    }
+
+    fn handle_ping(&mut self, ping: Ping) {
+        let Ping { id, request_id, payload, reply } = ping;
+        let check = self.settings.check(&id);
+        let now = self.ctx.clock.now();
+        let delay = check.delay_until_deadline(now, now);
+        let deadline = delay.map(|d| now + d);
+
+        debug!(id = id, request_id = request_id; "received ping for {} (request {}); timeout is {:?}", id, request_id, delay);
+
+        let previous = {
+            let mut checks = self.ctx.check_state.lock().unwrap();
+
+            if !checks.contains_key(&id) && self.exceeds_max_checks(&checks, &id) {
+                warn!(id = id, request_id = request_id; "maximum number of checks reached; ignoring ping for unknown check {} (request {})", id, request_id);
+                return;
+            }
+
+            self.ctx.record(&id, EventKind::Ping, Some(&request_id));
+
+            if let Some(state) = checks.get_mut(&id) {
+                if state.status == CheckStatus::Paused {
+                    debug!(id = id, request_id = request_id; "check {} is paused; not re-arming its timer (request {})", id, request_id);
+                    state.last_ping = Some(now);
+                    state.last_payload = payload;
+                    self.save(&checks, &id);
+                    send_deadline(reply, None);
+                    return;
+                }
+
+                // The check stays alerted (and acknowledged) until a ping
+                // arrives comfortably before its deadline.
+                if state.status == CheckStatus::Alerted && !check.recovers_on_ping(state.last_ping, now) {
+                    info!(id = id, request_id = request_id; "received ping for {} too close to its deadline; keeping it alerted (request {})", id, request_id);
+                    state.last_ping = Some(now);
+                    state.last_payload = payload;
+                    self.save(&checks, &id);
+                    drop(checks);
+
+                    send_deadline(reply, deadline);
+                    self.rearm(id, check, delay);
+                    return;
+                }
+            }
+
+            // Declared checks that never pinged are waiting for their
+            // registration as well; their timer from startup is cancelled,
+            // as their schedule has not begun yet.
+            let registering = check.register_first && checks.get(&id)
+                .is_none_or(|s| s.status == CheckStatus::Healthy && s.last_ping.is_none());
+
+            if registering {
+                info!(id = id, request_id = request_id; "check {} registered; its timer is armed by its next ping (request {})", id, request_id);
+                checks.insert(id.clone(), CheckState {
+                    status: CheckStatus::Registered,
+                    last_ping: Some(now),
+                    last_payload: payload,
+                    source: self.source(&id),
+                    labels: check.labels.clone(),
+                    group: check.group.clone(),
+                    depends_on: check.depends_on.clone(),
+                    consecutive_misses: 0,
+                    acknowledged: false,
+                    paused_while_alerted: false,
+                });
+                self.save(&checks, &id);
+                drop(checks);
+
+                self.active_timers.remove(&id);
+                send_deadline(reply, None);
+                return;
+            }
+
+            let previous = checks.insert(id.clone(), CheckState {
+                status: CheckStatus::Healthy,
+                last_ping: Some(now),
+                last_payload: payload,
+                source: self.source(&id),
+                labels: check.labels.clone(),
+                group: check.group.clone(),
+                depends_on: check.depends_on.clone(),
+                consecutive_misses: 0,
+                acknowledged: false,
+                paused_while_alerted: false,
+            });
+
+            self.save(&checks, &id);
+
+            previous
+        };
+
+        if let Some(CheckState { status: CheckStatus::Alerted, consecutive_misses, last_ping, .. }) = previous {
+            self.ctx.metrics.dec_checks_overdue();
+            info!(id = id, event = "recovery", request_id = request_id; "received ping for {} after missed ping; scheduling recovery (request {})", id, request_id);
+
+            let alert = recovery_alert(&id, &check, consecutive_misses, last_ping, now);
+            self.ctx.record(&id, EventKind::Recovery, Some(&request_id));
+
+            match queue_alert(&self.ctx.tx_alert, alert) {
+                Ok(_) => debug!(id = id; "recovery scheduled for {}", id),
+                Err(e) => warn!("error while scheduling recovery: {}", e)
+            }
+        }
+
+        send_deadline(reply, deadline);
+        self.rearm(id, check, delay);
+    }
+
+    /// Replaces the check's timer after a ping; `delay` is `None` if the
+    /// check is not expected to ping again.
+    fn rearm(&mut self, id: String, check: ResolvedCheck, delay: Option<chrono::Duration>) {
+        match delay {
+            Some(delay) => self.arm(id, check, delay),
+            None => {
+                debug!(id = id; "check {} is not expected to ping again", id);
+                self.active_timers.remove(&id);
+            },
+        }
+    }
+
+    fn handle_command(&mut self, cmd: Command) {
+        match cmd {
+            Command::Pause { id, reply } => {
+                let _ = reply.send(self.pause(&id));
+            },
+            Command::Resume { id, reply } => {
+                let _ = reply.send(self.resume(&id));
+            },
+            Command::Acknowledge { id, reply } => {
+                let _ = reply.send(self.acknowledge(&id));
+            },
+            Command::List { reply } => {
+                let _ = reply.send(self.read_checks(None));
+            },
+            Command::Describe { id, reply } => {
+                let _ = reply.send(self.describe(&id));
+            },
+            Command::Delete { id, reply } => {
+                let _ = reply.send(self.delete(&id));
+            },
+            Command::Reload { settings, notifier } => self.reload(*settings, notifier),
+            Command::TestNotification { reply } => self.test_notification(reply),
+            Command::Events { id, limit, reply } => {
+                let _ = reply.send(self.ctx.events.recent(id.as_deref(), limit));
+            },
+            Command::Reset { reply } => {
+                let _ = reply.send(self.reset());
+            },
+        }
+    }
+
+    /// Notifies in a separate task, so that slow notifiers do not hold up
+    /// pings.
+    fn test_notification(&self, reply: oneshot::Sender<Result<(), DodemansknopError>>) {
+        let notifier = self.notifier.read().unwrap().clone();
+        let alert = Alert {
+            id: TEST_CHECK_ID.to_string(),
+            event: AlertEvent::Failure,
+            targets: None,
+            payload: Some("This is a test notification sent by dodemansknop.".to_string()),
+            last_ping: None,
+            labels: HashMap::new(),
+            severity: Severity::default(),
+            consecutive_misses: 0,
+            message: None,
+            timestamp: self.ctx.clock.now(),
+        };
+
+        info!(id = TEST_CHECK_ID, event = "failure"; "sending test notification");
+
+        tokio::spawn(async move {
+            let _ = reply.send(notifier.notify_failure(alert).await);
+        });
+    }
+
+    /// Applies new settings and notifiers. Timers are only re-armed for
+    /// checks whose settings changed, and keep their deadline relative to
+    /// the last ping; so unless a check's interval changed, its deadline
+    /// remains the same.
+    fn reload(&mut self, settings: Settings, notifier: Arc<CompositeNotifier>) {
+        *self.notifier.write().unwrap() = notifier;
+
+        let old_settings = std::mem::replace(&mut self.settings, settings);
+
+        let checks: Vec<(String, CheckState)> = self.ctx.check_state.lock().unwrap()
+            .iter()
+            .map(|(id, state)| (id.clone(), state.clone()))
+            .collect();
+
+        let mut rearmed = 0;
+        for (id, state) in checks {
+            let check = self.settings.check(&id);
+            if check == old_settings.check(&id) {
+                continue;
+            }
+
+            if let Some(delay) = remaining_delay(&state, &check, self.ctx.clock.now()) {
+                debug!(id = id; "settings of check {} changed; timeout is {}", id, delay);
+                self.arm(id, check, delay);
+                rearmed += 1;
+            }
+        }
+
+        self.declare();
+
+        info!("configuration reloaded; re-armed {} timers", rearmed);
+    }
+
+    fn delete(&mut self, id: &str) -> bool {
+        let mut checks = self.ctx.check_state.lock().unwrap();
+
+        let state = match checks.remove(id) {
+            Some(s) => s,
+            None => return false,
+        };
+
+        if state.status == CheckStatus::Alerted {
+            self.ctx.metrics.dec_checks_overdue();
+        }
+
+        self.active_timers.remove(id);
+        self.save(&checks, id);
+        drop(checks);
+
+        self.ctx.record(id, EventKind::Delete, None);
+        info!(id = id; "deleted check {}", id);
+
+        // The alert of a deleted check would never be resolved otherwise.
+        if state.status == CheckStatus::Alerted || state.paused_while_alerted {
+            info!(id = id, event = "recovery"; "deleted check {} was alerted; scheduling recovery", id);
+
+            let recovery = recovery_alert(id, &self.settings.check(id), state.consecutive_misses, state.last_ping, self.ctx.clock.now());
+            if self.ctx.tx_alert.send(AlerterMessage::Deleted { recovery, group: state.group }).is_err() {
+                warn!("error while scheduling recovery: channel closed");
+            }
+        }
+
+        true
+    }
+
+    /// Forgets all checks, as if the server started without any state. The
+    /// declared checks are then tracked again, timed from now, like at
+    /// startup. Returns the number of checks forgotten.
+    fn reset(&mut self) -> usize {
+        let cleared = {
+            let mut checks = self.ctx.check_state.lock().unwrap();
+
+            let overdue = checks.values().filter(|s| s.status == CheckStatus::Alerted).count();
+            for _ in 0..overdue {
+                self.ctx.metrics.dec_checks_overdue();
+            }
+
+            let cleared = checks.len();
+            checks.clear();
+
+            if let Some(ref store) = self.ctx.store {
+                store.save_all(&checks);
+            }
+
+            cleared
+        };
+
+        self.active_timers.clear();
+        warn!("reset: forgot {} checks", cleared);
+
+        // Otherwise, the alerter would still consider groups down, or
+        // failures notified, and suppress the next alerts accordingly.
+        if self.ctx.tx_alert.send(AlerterMessage::Reset).is_err() {
+            warn!("error while resetting the alerter: channel closed");
+        }
+
+        self.declare();
+        cleared
+    }
+
+    /// Returns the state of all checks, or only of `id`, from the store if
+    /// it can be queried (i.e. with the SQLite backend), and from memory
+    /// otherwise.
+    fn read_checks(&self, id: Option<&str>) -> HashMap<String, CheckState> {
+        if let Some(checks) = self.ctx.store.as_ref().and_then(|store| store.query(id)) {
+            return checks;
+        }
+
+        let checks = self.ctx.check_state.lock().unwrap();
+        match id {
+            Some(id) => checks.get(id).map(|state| (id.to_string(), state.clone())).into_iter().collect(),
+            None => checks.clone(),
+        }
+    }
+
+    fn describe(&self, id: &str) -> Option<CheckDetail> {
+        let state = self.read_checks(Some(id)).remove(id)?;
+        let check = self.settings.check(id);
+
+        let condition = match state.status {
+            CheckStatus::Paused => CheckCondition::Paused,
+            CheckStatus::Alerted if state.acknowledged => CheckCondition::Acknowledged,
+            CheckStatus::Alerted => CheckCondition::Overdue,
+            CheckStatus::Healthy => CheckCondition::Healthy,
+            CheckStatus::Registered => CheckCondition::Registered,
+        };
+
+        // Declared checks that never pinged are timed from startup, which
+        // is not recorded; their deadline is not known.
+        let now = self.ctx.clock.now();
+        let remaining = match (state.status, state.last_ping) {
+            (CheckStatus::Healthy, Some(last_ping)) => check.delay_until_deadline(last_ping, now),
+            _ => None,
+        };
+
+        Some(CheckDetail {
+            id: id.to_string(),
+            state: condition,
+            status: state.status,
+            last_ping: state.last_ping,
+            deadline: remaining.map(|r| now + r),
+            seconds_until_deadline: remaining.map(|r| r.num_seconds()),
+            consecutive_misses: state.consecutive_misses,
+            source: state.source,
+            labels: state.labels,
+        })
+    }
+
+    fn acknowledge(&mut self, id: &str) -> bool {
+        let mut checks = self.ctx.check_state.lock().unwrap();
+
+        let state = match checks.get_mut(id) {
+            Some(s) => s,
+            None => return false,
+        };
+
+        state.acknowledged = true;
+        self.save(&checks, id);
+
+        self.ctx.record(id, EventKind::Acknowledge, None);
+        info!(id = id; "acknowledged check {}", id);
+        true
+    }
+
+    fn pause(&mut self, id: &str) -> bool {
+        let mut checks = self.ctx.check_state.lock().unwrap();
+
+        let state = match checks.get_mut(id) {
+            Some(s) => s,
+            None => return false,
+        };
+
+        if state.status == CheckStatus::Alerted {
+            self.ctx.metrics.dec_checks_overdue();
+            state.paused_while_alerted = true;
+        }
+
+        state.status = CheckStatus::Paused;
+        self.active_timers.remove(id);
+        self.save(&checks, id);
+
+        self.ctx.record(id, EventKind::Pause, None);
+        info!(id = id; "paused check {}", id);
+        true
+    }
+
+    fn resume(&mut self, id: &str) -> bool {
+        let mut checks = self.ctx.check_state.lock().unwrap();
+
+        let state = match checks.get_mut(id) {
+            Some(s) => s,
+            None => return false,
+        };
+
+        if state.status != CheckStatus::Paused {
+            return true;
+        }
+
+        let previous = state.clone();
+
+        state.status = CheckStatus::Healthy;
+        let now = self.ctx.clock.now();
+        state.last_ping = Some(now);
+        state.consecutive_misses = 0;
+        state.acknowledged = false;
+        state.paused_while_alerted = false;
+        self.save(&checks, id);
+        drop(checks);
+
+        let check = self.settings.check(id);
+
+        self.ctx.record(id, EventKind::Resume, None);
+        info!(id = id; "resumed check {}", id);
+
+        // Like a fresh ping, resuming resolves the alert the check was paused
+        // with.
+        if previous.paused_while_alerted {
+            info!(id = id, event = "recovery"; "resumed check {} was alerted when it was paused; scheduling recovery", id);
+
+            let alert = recovery_alert(id, &check, previous.consecutive_misses, previous.last_ping, now);
+            self.ctx.record(id, EventKind::Recovery, None);
+
+            if let Err(e) = queue_alert(&self.ctx.tx_alert, alert) {
+                warn!("error while scheduling recovery: {}", e);
+            }
+        }
+
+        if let Some(delay) = check.delay_until_deadline(now, now) {
+            self.arm(id.to_string(), check, delay);
+        }
+
+        true
+    }
+
+    fn arm(&mut self, id: String, check: ResolvedCheck, delay: chrono::Duration) {
+        match check.cron {
+            Some(ref cron) => debug!(id = id; "scheduling timeout for {} in {}; schedule is '{}' ({})", id, delay, cron, self.settings.interval_origin(&id)),
+            None => debug!(id = id; "scheduling timeout for {} in {}; interval is {}s ({})", id, delay, check.interval_seconds, self.settings.interval_origin(&id)),
+        }
+
+        let handle = schedule_timeout(&self.ctx, id.clone(), check, delay);
+        self.active_timers.insert(id, handle);
+    }
+
+    fn save(&self, checks: &HashMap<String, CheckState>, id: &str) {
+        if let Some(ref store) = self.ctx.store {
+            store.save(checks, id);
+        }
+    }
+}
+
+fn send_deadline(reply: Option<oneshot::Sender<Option<DateTime<Utc>>>>, deadline: Option<DateTime<Utc>>) {
+    if let Some(reply) = reply {
+        let _ = reply.send(deadline);
+    }
+}
+
+/// Returns the time until the next timeout of a check in `state`, or `None`
+/// if the check should not have a timer at all.
+fn remaining_delay(state: &CheckState, check: &ResolvedCheck, now: chrono::DateTime<chrono::Utc>) -> Option<chrono::Duration> {
+    match state.status {
+        CheckStatus::Paused | CheckStatus::Registered => None,
+        CheckStatus::Alerted => Some(check.repeat_interval(now)),
+        // Declared checks that never pinged are timed from startup.
+        CheckStatus::Healthy => check.delay_until_deadline(state.last_ping.unwrap_or(now), now),
+    }
+}
+
+/// Schedules a timeout for the check `id` that first fires after `delay`,
+/// and then repeatedly with the check's interval, counting the consecutive
+/// missed intervals. The first miss raises an alert; reaching the check's
+/// `escalate_after` threshold raises an escalated alert. With
+/// `repeat_until_ack`, the alert is repeated in between. The timeout is
+/// cancelled when the returned handle is dropped, which also ends the
+/// repetitions once the check pings again.
+fn schedule_timeout(ctx: &TimeoutContext, id: String, check: ResolvedCheck, delay: chrono::Duration) -> TimerHandle {
+    let ctx = ctx.clone();
+    let interval = check.repeat_interval(ctx.clock.now()).to_std().unwrap_or_default();
+    let mut next = Instant::now() + delay.to_std().unwrap_or_default();
+
+    TimerHandle(tokio::spawn(async move {
+        // When the alert is repeated next, and how often it was already.
+        let mut repeat: Option<(Instant, u32)> = None;
+
+        loop {
+            if let Some((at, n)) = repeat.filter(|(at, _)| *at < next) {
+                sleep_until(at).await;
+                repeat = repeat_failure(&ctx, &id, &check, n);
+                continue;
+            }
+
+            sleep_until(next).await;
+            next += interval;
+
+            // A panic would end the timer silently, so that the check never
+            // alerts again; it is logged instead, and the timer keeps going.
+            match catch_unwind(AssertUnwindSafe(|| handle_timeout(&ctx, &id, &check))) {
+                Ok(true) => {
+                    repeat = check.repeat_until_ack.as_ref().map(|r| (Instant::now() + r.delay(0), 0));
+                },
+                Ok(false) => {},
+                Err(_) => error!(id = id; "handling the timeout of {} panicked; its alert may not have been sent", id),
+            }
+        }
+    }))
+}
+
+/// Repeats the failure alert of check `id` for the `n`th time (counted from
+/// zero), unless it was acknowledged in the meantime. Returns when to repeat
+/// it next, or `None` if it is not repeated anymore.
+fn repeat_failure(ctx: &TimeoutContext, id: &str, check: &ResolvedCheck, n: u32) -> Option<(Instant, u32)> {
+    let repeat = check.repeat_until_ack.as_ref()?;
+
+    let (misses, payload, last_ping) = {
+        let checks = ctx.check_state.lock().unwrap();
+        match checks.get(id) {
+            Some(state) if state.status == CheckStatus::Alerted && !state.acknowledged => (state.consecutive_misses, state.last_payload.clone(), state.last_ping),
+            _ => {
+                debug!(id = id; "check {} is acknowledged or no longer failing; not repeating its alert", id);
+                return None;
+            },
+        }
+    };
+
+    info!(id = id, event = "failure"; "check {} is still failing and not acknowledged; repeating alert ({} of {})", id, n + 1, repeat.max_repeats);
+    ctx.record(id, EventKind::Repeat, None);
+    send_failure(ctx, id, check, check.notifiers.clone(), misses, payload, last_ping);
+
+    if n + 1 >= repeat.max_repeats {
+        info!(id = id; "alert for {} was repeated {} times; not repeating it anymore", id, repeat.max_repeats);
+        return None;
+    }
+
+    Some((Instant::now() + repeat.delay(n + 1), n + 1))
+}
+
+/// Counts a missed interval of check `id`, and returns whether this raised
+/// a new alert.
+fn handle_timeout(ctx: &TimeoutContext, id: &str, check: &ResolvedCheck) -> bool {
+    let (newly_alerted, misses, payload, last_ping) = {
+        let mut checks = ctx.check_state.lock().unwrap();
+        let result = match checks.get_mut(id) {
+            Some(state) if state.status != CheckStatus::Paused => {
+                state.consecutive_misses += 1;
+
+                let newly_alerted = state.status != CheckStatus::Alerted;
+                if newly_alerted {
+                    state.status = CheckStatus::Alerted;
+                    ctx.metrics.inc_checks_overdue();
+                }
+
+                (newly_alerted, state.consecutive_misses, state.last_payload.clone(), state.last_ping)
+            },
+            _ => return false,
+        };
+
+        if let Some(ref store) = ctx.store {
+            store.save(&checks, id);
+        }
+
+        result
+    };
+
+    if newly_alerted {
+        info!(id = id, event = "failure"; "missed ping for {}; scheduling alert", id);
+        ctx.record(id, EventKind::Failure, None);
+        send_failure(ctx, id, check, check.notifiers.clone(), misses, payload.clone(), last_ping);
+    } else {
+        debug!(id = id; "still missing ping for {} ({} consecutive misses)", id, misses);
+    }
+
+    if check.escalate_after == Some(misses) {
+        info!(id = id, event = "failure"; "missed {} consecutive pings for {}; scheduling escalation", misses, id);
+        ctx.record(id, EventKind::Escalation, None);
+        send_failure(ctx, id, check, check.escalation_notifiers.clone(), misses, payload, last_ping);
+    }
+
+    newly_alerted
+}
+
+/// Builds the recovery of check `id`, which had missed `consecutive_misses`
+/// intervals since its `last_ping`.
+fn recovery_alert(id: &str, check: &ResolvedCheck, consecutive_misses: u32, last_ping: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Alert {
+    // Escalated alerts need to be resolved with the escalation notifiers as
+    // well.
+    let escalated = check.escalate_after.is_some_and(|n| consecutive_misses >= n);
+    let targets = match (check.notifiers.clone(), check.escalation_notifiers.clone()) {
+        (Some(mut regular), Some(escalation)) if escalated => {
+            for name in escalation {
+                if !regular.contains(&name) {
+                    regular.push(name);
+                }
+            }
+            Some(regular)
+        },
+        (regular, _) if !escalated => regular,
+        _ => None,
+    };
+
+    Alert {
+        id: id.to_string(),
+        event: AlertEvent::Recovery,
+        targets,
+        payload: None,
+        last_ping: None,
+        labels: check.labels.clone(),
+        severity: check.severity,
+        consecutive_misses,
+        message: check.message(AlertEvent::Recovery, id, last_ping, now),
+        timestamp: now,
+    }
+}
+
+fn send_failure(ctx: &TimeoutContext, id: &str, check: &ResolvedCheck, targets: Option<Vec<String>>, misses: u32, payload: Option<String>, last_ping: Option<DateTime<Utc>>) {
+    ctx.metrics.inc_alerts_fired();
+
+    let now = ctx.clock.now();
+    let alert = Alert{
+        id: id.to_string(),
+        event: AlertEvent::Failure,
+        targets,
+        payload,
+        last_ping,
+        labels: check.labels.clone(),
+        severity: check.severity,
+        consecutive_misses: misses,
+        message: check.message(AlertEvent::Failure, id, last_ping, now),
+        timestamp: now,
+    };
+
+    match queue_alert(&ctx.tx_alert, alert) {
+        Ok(_) => debug!(id = id; "alert scheduled for {}", id),
+        Err(e) => warn!("error while scheduling alert: {}", e)
+    }
+}
+
+/// Hands an alert to the alerter task.
+fn queue_alert(tx_alert: &mpsc::UnboundedSender<AlerterMessage>, alert: Alert) -> Result<(), DodemansknopError> {
+    tx_alert.send(AlerterMessage::Alert(alert))?;
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    /// The check pinged within its interval.
+    Healthy,
+
+    /// The check missed its ping and a failure has been notified.
+    Alerted,
+
+    /// Monitoring of the check has been suspended via the API.
+    Paused,
+
+    /// The check only registered itself with its first ping, as it is
+    /// configured with `register_first`; its timer is armed by the next one.
+    Registered,
+}
+
+/// How dodemansknop learned about a check.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckSource {
+    /// The check is listed in the `checks` section of the configuration.
+    Declared,
+
+    /// The check is only known because it pinged.
+    #[default]
+    Discovered,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CheckState {
+    pub status: CheckStatus,
+
+    /// Time of the last ping; `None` for declared checks that have not
+    /// pinged yet.
+    pub last_ping: Option<DateTime<Utc>>,
+
+    /// Body of the last ping, if it had one.
+    #[serde(default)]
+    pub last_payload: Option<String>,
+
+    #[serde(default)]
+    pub source: CheckSource,
+
+    /// Labels of the check, as configured.
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+
+    /// Number of intervals that passed without a ping since the last ping.
+    #[serde(default)]
+    pub consecutive_misses: u32,
+
+    /// Group of the check, as configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+
+    /// IDs of the checks this check depends on, as configured.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+
+    /// Whether further failure notifications have been silenced via the
+    /// API. Cleared by the next ping.
+    #[serde(default)]
+    pub acknowledged: bool,
+
+    /// Whether the check was alerted when it was paused, so that resuming
+    /// (or deleting) it resolves the alert.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub paused_while_alerted: bool,
+}
+
+/// State of all known checks, keyed by check ID. This is written by the ping
+/// receiver thread (and the timers it schedules) and read by the HTTP API.
+pub type SharedState = Arc<Mutex<HashMap<String, CheckState>>>;
+
+#[derive(Serialize)]
+pub struct CheckInfo<'a> {
+    pub id: &'a str,
+
+    #[serde(flatten)]
+    pub state: &'a CheckState,
+}
+
+/// Condition of a check as reported by the API, combining its status with
+/// its acknowledgement.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckCondition {
+    Healthy,
+    Overdue,
+    Paused,
+    Acknowledged,
+    Registered,
+}
+
+/// Detailed view of a single check, including its next deadline.
+#[derive(Debug, Serialize)]
+pub struct CheckDetail {
+    pub id: String,
+    pub state: CheckCondition,
+    pub status: CheckStatus,
+    pub last_ping: Option<DateTime<Utc>>,
+
+    /// Time at which the check becomes overdue; `None` if it already is,
+    /// is paused, or its deadline is not known.
+    pub deadline: Option<DateTime<Utc>>,
+    pub seconds_until_deadline: Option<i64>,
+
+    pub consecutive_misses: u32,
+    pub source: CheckSource,
+    pub labels: HashMap<String, String>,
+}
+
+pub fn new_shared_state() -> SharedState {
+    Arc::new(Mutex::new(HashMap::new()))
+}
+
+/// Loads persisted check state from `path`. A missing or unreadable file is
+/// not an error; in that case, a warning is logged and an empty state is
+/// returned.
+pub fn load_state(path: &str) -> HashMap<String, CheckState> {
+    let contents = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) => {
+            warn!("could not read state file {}: {}; starting with empty state", path, e);
+            return HashMap::new();
+        }
+    };
+
+    match serde_json::from_str(&contents) {
+        Ok(s) => s,
+        Err(e) => {
+            warn!("could not parse state file {}: {}; starting with empty state", path, e);
+            HashMap::new()
+        }
+    }
+}
+
+/// Writes the check state to `path`. The file is replaced atomically, so
+/// that a crash while writing does not leave a corrupt state file behind.
+pub fn save_state(path: &str, checks: &HashMap<String, CheckState>) {
+    let tmp_path = format!("{}.tmp", path);
+
+    let result = serde_json::to_vec(checks)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| fs::write(&tmp_path, contents).map_err(|e| e.to_string()))
+        .and_then(|_| fs::rename(&tmp_path, path).map_err(|e| e.to_string()));
+
+    if let Err(e) = result {
+        warn!("could not write state file {}: {}", path, e);
+    }
+}
@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use log::warn;
+use rusqlite::{params, Connection, Row};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::config::StateBackendSettings;
+use crate::error::DodemansknopError;
+use crate::state::{self, CheckState};
+
+/// Durable storage of the check state. The in-memory state stays
+/// authoritative; the store receives every change, and is read at startup
+/// to restore it. Stores that can be queried cheaply also answer the API's
+/// reads.
+pub trait StateStore: Send + Sync {
+    /// Returns all persisted checks. Errors are logged, and result in an
+    /// empty state.
+    fn load(&self) -> HashMap<String, CheckState>;
+
+    /// Persists the state of check `id` after it changed. `checks` holds
+    /// the state of all checks, and no longer contains `id` if the check was
+    /// deleted. Errors are logged.
+    fn save(&self, checks: &HashMap<String, CheckState>, id: &str);
+
+    /// Persists the state of all checks, e.g. after the configuration has
+    /// been applied to them. Errors are logged.
+    fn save_all(&self, checks: &HashMap<String, CheckState>);
+
+    /// Reads the persisted checks for the API: all of them, or only `id`.
+    /// Returns `None` if the store is not queried, or on errors (which are
+    /// logged); the in-memory state, which mirrors it, is read instead.
+    fn query(&self, _id: Option<&str>) -> Option<HashMap<String, CheckState>> {
+        None
+    }
+}
+
+/// Opens the store configured by `settings`.
+pub fn open(settings: &StateBackendSettings) -> Result<Box<dyn StateStore>, DodemansknopError> {
+    match settings {
+        StateBackendSettings::Json { path } => Ok(Box::new(JsonStore { path: path.clone() })),
+        StateBackendSettings::Sqlite { path } => Ok(Box::new(SqliteStore::open(path)?)),
+    }
+}
+
+/// Stores all checks in a single JSON file, which is rewritten on every
+/// change.
+pub struct JsonStore {
+    path: String,
+}
+
+impl StateStore for JsonStore {
+    fn load(&self) -> HashMap<String, CheckState> {
+        state::load_state(&self.path)
+    }
+
+    fn save(&self, checks: &HashMap<String, CheckState>, _id: &str) {
+        state::save_state(&self.path, checks);
+    }
+
+    fn save_all(&self, checks: &HashMap<String, CheckState>) {
+        state::save_state(&self.path, checks);
+    }
+}
+
+/// Stores each check as a row of the `checks` table in an SQLite database,
+/// so that only the changed check is written, and the state can be queried
+/// with SQL. Labels and dependencies are stored as JSON.
+pub struct SqliteStore {
+    path: String,
+    conn: Mutex<Connection>,
+}
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS checks (
+        id                 TEXT PRIMARY KEY NOT NULL,
+        status             TEXT NOT NULL,
+        last_ping          TEXT,
+        last_payload       TEXT,
+        source             TEXT NOT NULL,
+        consecutive_misses INTEGER NOT NULL DEFAULT 0,
+        acknowledged       INTEGER NOT NULL DEFAULT 0,
+        paused_while_alerted INTEGER NOT NULL DEFAULT 0,
+        check_group        TEXT,
+        depends_on         TEXT NOT NULL DEFAULT '[]',
+        labels             TEXT NOT NULL DEFAULT '{}'
+    );
+";
+
+const UPSERT: &str = "
+    INSERT INTO checks (id, status, last_ping, last_payload, source, consecutive_misses, acknowledged, check_group, depends_on, labels, paused_while_alerted)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+    ON CONFLICT (id) DO UPDATE SET
+        status = excluded.status,
+        last_ping = excluded.last_ping,
+        last_payload = excluded.last_payload,
+        source = excluded.source,
+        consecutive_misses = excluded.consecutive_misses,
+        acknowledged = excluded.acknowledged,
+        check_group = excluded.check_group,
+        depends_on = excluded.depends_on,
+        labels = excluded.labels,
+        paused_while_alerted = excluded.paused_while_alerted
+";
+
+/// Columns added after the table was introduced, which databases created by
+/// earlier versions lack.
+const ADDED_COLUMNS: &[(&str, &str)] = &[
+    ("paused_while_alerted", "INTEGER NOT NULL DEFAULT 0"),
+];
+
+impl SqliteStore {
+    /// Opens the database at `path`, creating it and its table if necessary.
+    pub fn open(path: &str) -> Result<Self, DodemansknopError> {
+        let error = |e: rusqlite::Error| DodemansknopError::Config(format!("could not open state database {}: {}", path, e));
+
+        let conn = Connection::open(path).map_err(error)?;
+
+        // With a write-ahead log, a crash never leaves a partially written
+        // transaction behind, and readers do not block the writer.
+        conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(())).map_err(error)?;
+        conn.execute_batch(SCHEMA).map_err(error)?;
+
+        for (column, definition) in ADDED_COLUMNS {
+            if conn.prepare(&format!("SELECT {} FROM checks LIMIT 0", column)).is_err() {
+                conn.execute_batch(&format!("ALTER TABLE checks ADD COLUMN {} {}", column, definition)).map_err(error)?;
+            }
+        }
+
+        Ok(Self {
+            path: path.to_string(),
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn upsert(conn: &Connection, id: &str, state: &CheckState) -> rusqlite::Result<()> {
+        conn.execute(UPSERT, params![
+            id,
+            to_text(&state.status),
+            state.last_ping.map(|t| t.to_rfc3339()),
+            state.last_payload,
+            to_text(&state.source),
+            state.consecutive_misses,
+            state.acknowledged,
+            state.group,
+            serde_json::to_string(&state.depends_on).unwrap_or_default(),
+            serde_json::to_string(&state.labels).unwrap_or_default(),
+            state.paused_while_alerted,
+        ])?;
+
+        Ok(())
+    }
+
+    fn read_row(row: &Row) -> rusqlite::Result<(String, CheckState)> {
+        let id: String = row.get("id")?;
+        let last_ping: Option<String> = row.get("last_ping")?;
+
+        let state = CheckState {
+            status: from_text(row, "status")?,
+            last_ping: last_ping.map(|t| parse_time(row, &t)).transpose()?,
+            last_payload: row.get("last_payload")?,
+            source: from_text(row, "source")?,
+            labels: from_json(row, "labels")?,
+            consecutive_misses: row.get("consecutive_misses")?,
+            group: row.get("check_group")?,
+            depends_on: from_json(row, "depends_on")?,
+            acknowledged: row.get("acknowledged")?,
+            paused_while_alerted: row.get("paused_while_alerted")?,
+        };
+
+        Ok((id, state))
+    }
+
+    fn try_load(&self, id: Option<&str>) -> rusqlite::Result<HashMap<String, CheckState>> {
+        let conn = self.conn.lock().unwrap();
+
+        match id {
+            Some(id) => conn.prepare("SELECT * FROM checks WHERE id = ?1")?.query_map([id], Self::read_row)?.collect(),
+            None => conn.prepare("SELECT * FROM checks")?.query_map([], Self::read_row)?.collect(),
+        }
+    }
+
+    fn try_save(&self, checks: &HashMap<String, CheckState>, id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().unwrap();
+
+        match checks.get(id) {
+            Some(state) => Self::upsert(&conn, id, state),
+            None => conn.execute("DELETE FROM checks WHERE id = ?1", [id]).map(|_| ()),
+        }
+    }
+
+    fn try_save_all(&self, checks: &HashMap<String, CheckState>) -> rusqlite::Result<()> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+
+        {
+            let mut known = tx.prepare("SELECT id FROM checks")?;
+            let ids = known.query_map([], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+
+            for id in ids.iter().filter(|id| !checks.contains_key(*id)) {
+                tx.execute("DELETE FROM checks WHERE id = ?1", [id])?;
+            }
+        }
+
+        for (id, state) in checks {
+            Self::upsert(&tx, id, state)?;
+        }
+
+        tx.commit()
+    }
+}
+
+impl StateStore for SqliteStore {
+    fn load(&self) -> HashMap<String, CheckState> {
+        self.try_load(None).unwrap_or_else(|e| {
+            warn!("could not read state database {}: {}; starting with empty state", self.path, e);
+            HashMap::new()
+        })
+    }
+
+    fn save(&self, checks: &HashMap<String, CheckState>, id: &str) {
+        if let Err(e) = self.try_save(checks, id) {
+            warn!(id = id; "could not write check {} to state database {}: {}", id, self.path, e);
+        }
+    }
+
+    fn save_all(&self, checks: &HashMap<String, CheckState>) {
+        if let Err(e) = self.try_save_all(checks) {
+            warn!("could not write state database {}: {}", self.path, e);
+        }
+    }
+
+    fn query(&self, id: Option<&str>) -> Option<HashMap<String, CheckState>> {
+        self.try_load(id)
+            .map_err(|e| warn!("could not read state database {}: {}; reading in-memory state", self.path, e))
+            .ok()
+    }
+}
+
+/// Returns the serialized name of a unit enum variant, e.g. "healthy".
+fn to_text<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => s,
+        _ => String::new(),
+    }
+}
+
+fn from_text<T: DeserializeOwned>(row: &Row, column: &str) -> rusqlite::Result<T> {
+    let text: String = row.get(column)?;
+    serde_json::from_value(serde_json::Value::String(text))
+        .map_err(|e| conversion_error(row, column, e))
+}
+
+fn from_json<T: DeserializeOwned>(row: &Row, column: &str) -> rusqlite::Result<T> {
+    let text: String = row.get(column)?;
+    serde_json::from_str(&text)
+        .map_err(|e| conversion_error(row, column, e))
+}
+
+fn parse_time(row: &Row, text: &str) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| conversion_error(row, "last_ping", e))
+}
+
+fn conversion_error<E: std::error::Error + Send + Sync + 'static>(row: &Row, column: &str, e: E) -> rusqlite::Error {
+    let index = row.as_ref().column_index(column).unwrap_or_default();
+    rusqlite::Error::FromSqlConversionFailure(index, rusqlite::types::Type::Text, Box::new(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{CheckSource, CheckStatus};
+
+    fn check(status: CheckStatus) -> CheckState {
+        CheckState {
+            status,
+            last_ping: None,
+            last_payload: None,
+            source: CheckSource::Discovered,
+            labels: HashMap::from([("team".to_string(), "ops".to_string())]),
+            consecutive_misses: 0,
+            group: None,
+            depends_on: Vec::new(),
+            acknowledged: false,
+            paused_while_alerted: false,
+        }
+    }
+
+    #[test]
+    fn sqlite_store_answers_queries() {
+        let store = SqliteStore::open(":memory:").unwrap();
+        let checks = HashMap::from([
+            ("a".to_string(), check(CheckStatus::Healthy)),
+            ("b".to_string(), check(CheckStatus::Alerted)),
+        ]);
+        store.save_all(&checks);
+
+        let all = store.query(None).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all["b"].status, CheckStatus::Alerted);
+        assert_eq!(all["a"].labels["team"], "ops");
+
+        let one = store.query(Some("b")).unwrap();
+        assert_eq!(one.keys().collect::<Vec<_>>(), vec!["b"]);
+        assert!(store.query(Some("c")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn json_store_is_not_queried() {
+        let store = JsonStore { path: "/nonexistent/state.json".to_string() };
+        assert!(store.query(None).is_none());
+    }
+
+    #[test]
+    fn sqlite_store_adds_missing_columns() {
+        let path = std::env::temp_dir().join(format!("dodemansknop-{}.db", uuid::Uuid::new_v4()));
+        let path = path.to_str().unwrap();
+
+        Connection::open(path).unwrap().execute_batch("
+            CREATE TABLE checks (
+                id TEXT PRIMARY KEY NOT NULL, status TEXT NOT NULL, last_ping TEXT, last_payload TEXT,
+                source TEXT NOT NULL, consecutive_misses INTEGER NOT NULL DEFAULT 0,
+                acknowledged INTEGER NOT NULL DEFAULT 0, check_group TEXT,
+                depends_on TEXT NOT NULL DEFAULT '[]', labels TEXT NOT NULL DEFAULT '{}'
+            );
+            INSERT INTO checks (id, status, source) VALUES ('a', 'paused', 'declared');
+        ").unwrap();
+
+        let store = SqliteStore::open(path).unwrap();
+        let checks = store.load();
+        let _ = std::fs::remove_file(path);
+
+        assert_eq!(checks["a"].status, CheckStatus::Paused);
+        assert!(!checks["a"].paused_while_alerted);
+    }
+}